@@ -1,34 +1,232 @@
+use bytes::{Buf, BufMut, BytesMut};
+use futures::{SinkExt, StreamExt};
 use pyo3::prelude::*;
 use pyo3_async_runtimes::tokio::future_into_py;
+use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::mpsc;
+use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite};
 
 use crate::messages::{InboundMessage, OutboundMessage};
 
+/// What to do when a bounded queue is full and a new message arrives.
+/// Unbounded queues (the default) never reach this - it only matters once
+/// `inbound_capacity`/`outbound_capacity` is set.
+#[derive(Clone, Copy, Debug)]
+enum OverflowPolicy {
+    /// Wait for room, same as an unbounded queue filling up slowly.
+    Block,
+    /// Evict the oldest queued message to make room for the new one.
+    DropOldest,
+    /// Fail immediately with a `QueueFull` error.
+    Reject,
+}
+
+impl OverflowPolicy {
+    fn parse(value: Option<&str>) -> PyResult<Self> {
+        match value {
+            None | Some("block") => Ok(OverflowPolicy::Block),
+            Some("drop_oldest") => Ok(OverflowPolicy::DropOldest),
+            Some("reject") => Ok(OverflowPolicy::Reject),
+            Some(other) => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Invalid overflow_policy '{}': expected 'block', 'drop_oldest', or 'reject'",
+                other
+            ))),
+        }
+    }
+}
+
+/// A send half that's either capacity-bounded or unbounded, so `MessageBus`
+/// can offer backpressure without duplicating its publish logic per mode.
+enum QueueTx<T> {
+    Bounded(mpsc::Sender<T>),
+    Unbounded(mpsc::UnboundedSender<T>),
+}
+
+impl<T> QueueTx<T> {
+    async fn send(&self, msg: T) -> Result<(), T> {
+        match self {
+            QueueTx::Bounded(tx) => tx.send(msg).await.map_err(|e| e.0),
+            QueueTx::Unbounded(tx) => tx.send(msg).map_err(|e| e.0),
+        }
+    }
+
+    fn try_send(&self, msg: T) -> Result<(), mpsc::error::TrySendError<T>> {
+        match self {
+            QueueTx::Bounded(tx) => tx.try_send(msg),
+            QueueTx::Unbounded(tx) => tx
+                .send(msg)
+                .map_err(|e| mpsc::error::TrySendError::Closed(e.0)),
+        }
+    }
+
+    /// Configured capacity, or `None` for an unbounded queue.
+    fn capacity(&self) -> Option<usize> {
+        match self {
+            QueueTx::Bounded(tx) => Some(tx.max_capacity()),
+            QueueTx::Unbounded(_) => None,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        match self {
+            QueueTx::Bounded(tx) => tx.capacity() == 0,
+            QueueTx::Unbounded(_) => false,
+        }
+    }
+}
+
+/// Matching receive half of a [`QueueTx`].
+enum QueueRx<T> {
+    Bounded(mpsc::Receiver<T>),
+    Unbounded(mpsc::UnboundedReceiver<T>),
+}
+
+impl<T> QueueRx<T> {
+    async fn recv(&mut self) -> Option<T> {
+        match self {
+            QueueRx::Bounded(rx) => rx.recv().await,
+            QueueRx::Unbounded(rx) => rx.recv().await,
+        }
+    }
+
+    fn try_recv(&mut self) -> Result<T, mpsc::error::TryRecvError> {
+        match self {
+            QueueRx::Bounded(rx) => rx.try_recv(),
+            QueueRx::Unbounded(rx) => rx.try_recv(),
+        }
+    }
+}
+
+/// Publish `msg` onto `tx`, applying `policy` when `tx` is bounded and
+/// full. `rx` is only touched by the `DropOldest` policy, to evict the
+/// oldest queued message and make room.
+async fn publish<T: Send + 'static>(
+    tx: &QueueTx<T>,
+    rx: &Arc<tokio::sync::Mutex<QueueRx<T>>>,
+    count: &AtomicUsize,
+    policy: OverflowPolicy,
+    queue_name: &str,
+    msg: T,
+) -> PyResult<()> {
+    let closed_err = || {
+        pyo3::exceptions::PyRuntimeError::new_err(format!("{} queue closed", queue_name))
+    };
+
+    match policy {
+        OverflowPolicy::Block => {
+            tx.send(msg).await.map_err(|_| closed_err())?;
+            count.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+        OverflowPolicy::Reject => match tx.try_send(msg) {
+            Ok(()) => {
+                count.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => Err(closed_err()),
+            Err(mpsc::error::TrySendError::Full(_)) => Err(pyo3::exceptions::PyRuntimeError::new_err(
+                format!("QueueFull: {} queue is at capacity", queue_name),
+            )),
+        },
+        OverflowPolicy::DropOldest => match tx.try_send(msg) {
+            Ok(()) => {
+                count.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => Err(closed_err()),
+            Err(mpsc::error::TrySendError::Full(msg)) => {
+                let mut pending = msg;
+                loop {
+                    // Hold `rx`'s lock across the whole drop-then-resend
+                    // attempt, so two producers racing to free and claim
+                    // the same slot serialize on this lock instead of one
+                    // of them losing the resend to the other.
+                    let mut queue_rx = rx.lock().await;
+
+                    // Room may already be free (another producer's drop,
+                    // or the consumer draining) - try before evicting
+                    // anything.
+                    match tx.try_send(pending) {
+                        Ok(()) => {
+                            count.fetch_add(1, Ordering::Relaxed);
+                            return Ok(());
+                        }
+                        Err(mpsc::error::TrySendError::Closed(_)) => return Err(closed_err()),
+                        Err(mpsc::error::TrySendError::Full(m)) => pending = m,
+                    }
+
+                    if queue_rx.try_recv().is_ok() {
+                        count.fetch_sub(1, Ordering::Relaxed);
+                    }
+
+                    match tx.try_send(pending) {
+                        Ok(()) => {
+                            count.fetch_add(1, Ordering::Relaxed);
+                            return Ok(());
+                        }
+                        Err(mpsc::error::TrySendError::Closed(_)) => return Err(closed_err()),
+                        Err(mpsc::error::TrySendError::Full(m)) => {
+                            pending = m;
+                            drop(queue_rx);
+                        }
+                    }
+                }
+            }
+        },
+    }
+}
+
 /// Async message bus that decouples chat channels from the agent core.
 ///
 /// Channels push messages to the inbound queue, and the agent processes
-/// them and pushes responses to the outbound queue.
+/// them and pushes responses to the outbound queue. By default both
+/// queues are unbounded; passing `inbound_capacity`/`outbound_capacity`
+/// bounds them and applies `overflow_policy` once a queue fills up.
 #[pyclass]
 pub struct MessageBus {
-    inbound_tx: mpsc::UnboundedSender<InboundMessage>,
-    inbound_rx: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<InboundMessage>>>,
-    outbound_tx: mpsc::UnboundedSender<OutboundMessage>,
-    outbound_rx: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<OutboundMessage>>>,
+    inbound_tx: QueueTx<InboundMessage>,
+    inbound_rx: Arc<tokio::sync::Mutex<QueueRx<InboundMessage>>>,
+    outbound_tx: QueueTx<OutboundMessage>,
+    outbound_rx: Arc<tokio::sync::Mutex<QueueRx<OutboundMessage>>>,
     running: Arc<AtomicBool>,
     inbound_count: Arc<AtomicUsize>,
     outbound_count: Arc<AtomicUsize>,
+    overflow_policy: OverflowPolicy,
 }
 
 #[pymethods]
 impl MessageBus {
     #[new]
-    fn new() -> Self {
-        let (inbound_tx, inbound_rx) = mpsc::unbounded_channel();
-        let (outbound_tx, outbound_rx) = mpsc::unbounded_channel();
+    #[pyo3(signature = (inbound_capacity=None, outbound_capacity=None, overflow_policy=None))]
+    fn new(
+        inbound_capacity: Option<usize>,
+        outbound_capacity: Option<usize>,
+        overflow_policy: Option<&str>,
+    ) -> PyResult<Self> {
+        let (inbound_tx, inbound_rx) = match inbound_capacity {
+            Some(cap) => {
+                let (tx, rx) = mpsc::channel(cap);
+                (QueueTx::Bounded(tx), QueueRx::Bounded(rx))
+            }
+            None => {
+                let (tx, rx) = mpsc::unbounded_channel();
+                (QueueTx::Unbounded(tx), QueueRx::Unbounded(rx))
+            }
+        };
+        let (outbound_tx, outbound_rx) = match outbound_capacity {
+            Some(cap) => {
+                let (tx, rx) = mpsc::channel(cap);
+                (QueueTx::Bounded(tx), QueueRx::Bounded(rx))
+            }
+            None => {
+                let (tx, rx) = mpsc::unbounded_channel();
+                (QueueTx::Unbounded(tx), QueueRx::Unbounded(rx))
+            }
+        };
 
-        Self {
+        Ok(Self {
             inbound_tx,
             inbound_rx: Arc::new(tokio::sync::Mutex::new(inbound_rx)),
             outbound_tx,
@@ -36,23 +234,25 @@ impl MessageBus {
             running: Arc::new(AtomicBool::new(false)),
             inbound_count: Arc::new(AtomicUsize::new(0)),
             outbound_count: Arc::new(AtomicUsize::new(0)),
-        }
+            overflow_policy: OverflowPolicy::parse(overflow_policy)?,
+        })
     }
 
-    /// Publish a message from a channel to the agent.
+    /// Publish a message from a channel to the agent. Awaits available
+    /// capacity (or applies `overflow_policy`) when the inbound queue is
+    /// bounded and full.
     fn publish_inbound<'py>(
         &self,
         py: Python<'py>,
         msg: InboundMessage,
     ) -> PyResult<Bound<'py, PyAny>> {
-        let tx = self.inbound_tx.clone();
+        let tx = self.inbound_tx.clone_handle();
+        let rx = self.inbound_rx.clone();
         let count = self.inbound_count.clone();
+        let policy = self.overflow_policy;
 
         future_into_py(py, async move {
-            tx.send(msg)
-                .map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("Inbound queue closed"))?;
-            count.fetch_add(1, Ordering::Relaxed);
-            Ok(())
+            publish(&tx, &rx, &count, policy, "Inbound", msg).await
         })
     }
 
@@ -75,20 +275,21 @@ impl MessageBus {
         })
     }
 
-    /// Publish a response from the agent to channels.
+    /// Publish a response from the agent to channels. Awaits available
+    /// capacity (or applies `overflow_policy`) when the outbound queue is
+    /// bounded and full.
     fn publish_outbound<'py>(
         &self,
         py: Python<'py>,
         msg: OutboundMessage,
     ) -> PyResult<Bound<'py, PyAny>> {
-        let tx = self.outbound_tx.clone();
+        let tx = self.outbound_tx.clone_handle();
+        let rx = self.outbound_rx.clone();
         let count = self.outbound_count.clone();
+        let policy = self.overflow_policy;
 
         future_into_py(py, async move {
-            tx.send(msg)
-                .map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("Outbound queue closed"))?;
-            count.fetch_add(1, Ordering::Relaxed);
-            Ok(())
+            publish(&tx, &rx, &count, policy, "Outbound", msg).await
         })
     }
 
@@ -128,6 +329,30 @@ impl MessageBus {
         self.outbound_count.load(Ordering::Relaxed)
     }
 
+    /// Configured inbound capacity, or `None` if the queue is unbounded.
+    #[getter]
+    fn inbound_capacity(&self) -> Option<usize> {
+        self.inbound_tx.capacity()
+    }
+
+    /// Configured outbound capacity, or `None` if the queue is unbounded.
+    #[getter]
+    fn outbound_capacity(&self) -> Option<usize> {
+        self.outbound_tx.capacity()
+    }
+
+    /// Whether the inbound queue is bounded and currently at capacity.
+    #[getter]
+    fn is_inbound_full(&self) -> bool {
+        self.inbound_tx.is_full()
+    }
+
+    /// Whether the outbound queue is bounded and currently at capacity.
+    #[getter]
+    fn is_outbound_full(&self) -> bool {
+        self.outbound_tx.is_full()
+    }
+
     fn __repr__(&self) -> String {
         format!(
             "MessageBus(inbound_size={}, outbound_size={})",
@@ -136,3 +361,221 @@ impl MessageBus {
         )
     }
 }
+
+impl<T> QueueTx<T> {
+    /// Cheap clone of the send handle (channel senders are themselves
+    /// `Clone`; this just re-wraps the clone in the same enum variant).
+    fn clone_handle(&self) -> Self {
+        match self {
+            QueueTx::Bounded(tx) => QueueTx::Bounded(tx.clone()),
+            QueueTx::Unbounded(tx) => QueueTx::Unbounded(tx.clone()),
+        }
+    }
+}
+
+/// Largest JSON payload a frame may declare, guarding against a bogus or
+/// malicious length prefix trying to make us buffer unbounded memory.
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Pull one length-prefixed frame's payload out of `src`, or `None` if a
+/// full frame hasn't arrived yet. Shared by every codec below so the
+/// framing logic only has to be right in one place.
+fn read_frame(src: &mut BytesMut) -> std::io::Result<Option<BytesMut>> {
+    if src.len() < 4 {
+        return Ok(None);
+    }
+    let len = u32::from_be_bytes(src[..4].try_into().unwrap()) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("frame of {} bytes exceeds max {}", len, MAX_FRAME_LEN),
+        ));
+    }
+    if src.len() < 4 + len {
+        src.reserve(4 + len - src.len());
+        return Ok(None);
+    }
+    src.advance(4);
+    Ok(Some(src.split_to(len)))
+}
+
+/// Write `payload` to `dst` with its 4-byte big-endian length prefix.
+fn write_frame(payload: &[u8], dst: &mut BytesMut) {
+    dst.reserve(4 + payload.len());
+    dst.put_u32(payload.len() as u32);
+    dst.extend_from_slice(payload);
+}
+
+fn encode_err(e: impl std::fmt::Display) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireInbound {
+    channel: String,
+    sender_id: String,
+    chat_id: String,
+    content: String,
+    timestamp: f64,
+    media: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireOutbound {
+    channel: String,
+    chat_id: String,
+    content: String,
+    reply_to: Option<String>,
+    media: Vec<String>,
+}
+
+/// Length-delimited JSON framing for `InboundMessage`: a 4-byte
+/// big-endian length prefix followed by that many bytes of JSON, so an
+/// external TCP/Unix-socket client can speak the same protocol
+/// `publish_inbound` uses in-process. Python-side metadata isn't
+/// transportable over the wire and is dropped - see
+/// [`InboundMessage::from_wire`].
+#[derive(Default)]
+pub(crate) struct InboundCodec;
+
+impl Decoder for InboundCodec {
+    type Item = InboundMessage;
+    type Error = std::io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> std::io::Result<Option<InboundMessage>> {
+        let Some(payload) = read_frame(src)? else {
+            return Ok(None);
+        };
+        let wire: WireInbound = serde_json::from_slice(&payload).map_err(encode_err)?;
+        Ok(Some(InboundMessage::from_wire(
+            wire.channel,
+            wire.sender_id,
+            wire.chat_id,
+            wire.content,
+            wire.timestamp,
+            wire.media,
+        )))
+    }
+}
+
+impl Encoder<InboundMessage> for InboundCodec {
+    type Error = std::io::Error;
+
+    fn encode(&mut self, item: InboundMessage, dst: &mut BytesMut) -> std::io::Result<()> {
+        let wire = WireInbound {
+            channel: item.channel,
+            sender_id: item.sender_id,
+            chat_id: item.chat_id,
+            content: item.content,
+            timestamp: item.timestamp,
+            media: item.media,
+        };
+        let payload = serde_json::to_vec(&wire).map_err(encode_err)?;
+        write_frame(&payload, dst);
+        Ok(())
+    }
+}
+
+/// Write-side counterpart of [`InboundCodec`] for `OutboundMessage`.
+#[derive(Default)]
+pub(crate) struct OutboundCodec;
+
+impl Decoder for OutboundCodec {
+    type Item = OutboundMessage;
+    type Error = std::io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> std::io::Result<Option<OutboundMessage>> {
+        let Some(payload) = read_frame(src)? else {
+            return Ok(None);
+        };
+        let wire: WireOutbound = serde_json::from_slice(&payload).map_err(encode_err)?;
+        Ok(Some(OutboundMessage::from_wire(
+            wire.channel,
+            wire.chat_id,
+            wire.content,
+            wire.reply_to,
+            wire.media,
+        )))
+    }
+}
+
+impl Encoder<OutboundMessage> for OutboundCodec {
+    type Error = std::io::Error;
+
+    fn encode(&mut self, item: OutboundMessage, dst: &mut BytesMut) -> std::io::Result<()> {
+        let wire = WireOutbound {
+            channel: item.channel,
+            chat_id: item.chat_id,
+            content: item.content,
+            reply_to: item.reply_to,
+            media: item.media,
+        };
+        let payload = serde_json::to_vec(&wire).map_err(encode_err)?;
+        write_frame(&payload, dst);
+        Ok(())
+    }
+}
+
+impl MessageBus {
+    /// Spawn a task that reads length-delimited `InboundMessage` frames
+    /// from `stream` and publishes each onto the inbound queue (honoring
+    /// the same `overflow_policy` as `publish_inbound`), so a chat channel
+    /// running in another process can connect as a TCP/Unix-socket client
+    /// instead of calling into this `MessageBus` directly. Returns the
+    /// pump task's handle; the task exits once the stream closes or a
+    /// frame fails to decode.
+    pub fn attach_reader<R>(&self, stream: R) -> tokio::task::JoinHandle<()>
+    where
+        R: tokio::io::AsyncRead + Unpin + Send + 'static,
+    {
+        let tx = self.inbound_tx.clone_handle();
+        let rx = self.inbound_rx.clone();
+        let count = self.inbound_count.clone();
+        let policy = self.overflow_policy;
+
+        tokio::spawn(async move {
+            let mut framed = FramedRead::new(stream, InboundCodec);
+            while let Some(frame) = framed.next().await {
+                let msg = match frame {
+                    Ok(msg) => msg,
+                    Err(_) => break,
+                };
+                if publish(&tx, &rx, &count, policy, "Inbound", msg)
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Spawn a task that consumes the outbound queue and writes each
+    /// message to `stream` as a length-delimited `OutboundMessage` frame,
+    /// the write-side counterpart of `attach_reader`. Exits once the
+    /// outbound queue closes or a write fails.
+    pub fn attach_writer<W>(&self, stream: W) -> tokio::task::JoinHandle<()>
+    where
+        W: tokio::io::AsyncWrite + Unpin + Send + 'static,
+    {
+        let rx = self.outbound_rx.clone();
+        let count = self.outbound_count.clone();
+
+        tokio::spawn(async move {
+            let mut framed = FramedWrite::new(stream, OutboundCodec);
+            loop {
+                let msg = {
+                    let mut guard = rx.lock().await;
+                    match guard.recv().await {
+                        Some(msg) => msg,
+                        None => break,
+                    }
+                };
+                count.fetch_sub(1, Ordering::Relaxed);
+                if framed.send(msg).await.is_err() {
+                    break;
+                }
+            }
+        })
+    }
+}
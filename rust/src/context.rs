@@ -1,8 +1,11 @@
 //! Context builder for assembling agent prompts.
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use parking_lot::Mutex;
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList};
+use pyo3::types::{PyAny, PyDict, PyList};
+use regex::Regex;
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -12,6 +15,18 @@ use crate::skills::SkillsLoader;
 /// Bootstrap files to load from workspace.
 const BOOTSTRAP_FILES: &[&str] = &["AGENTS.md", "SOUL.md", "USER.md", "TOOLS.md", "IDENTITY.md"];
 
+/// Default number of trailing user turns `build_messages` keeps verbatim
+/// once history windowing kicks in (see `ContextBuilder::configure_history_budget`).
+const DEFAULT_RECENT_WINDOW: usize = 10;
+
+/// Character cap on the deterministic fallback history summary, matching
+/// `session.rs`'s `default_summary`.
+const MAX_SUMMARY_CHARS: usize = 500;
+
+/// Default byte cap applied to extracted document text / audio transcripts
+/// before they're inlined into a message (see `configure_attachments`).
+const DEFAULT_ATTACHMENT_MAX_BYTES: usize = 20_000;
+
 /// Builds the context (system prompt + messages) for the agent.
 ///
 /// Assembles bootstrap files, memory, skills, and conversation history
@@ -21,6 +36,96 @@ pub struct ContextBuilder {
     workspace: PathBuf,
     memory: MemoryStore,
     skills: SkillsLoader,
+    history_budget: Mutex<HistoryBudget>,
+    /// Per-session rolling-summary state for `windowed_history`, keyed by
+    /// the `session_key` callers pass to `build_messages` - unlike
+    /// `history_budget`'s config, `summarized_through`/`running_summary`
+    /// are specific to one conversation's history and must not bleed
+    /// between sessions sharing this workspace-scoped `ContextBuilder`.
+    history_state: Mutex<HashMap<String, HistoryState>>,
+    capabilities: Mutex<Capabilities>,
+    attachments: Mutex<AttachmentConfig>,
+    /// Ordered list of bootstrap file paths/glob patterns (relative to the
+    /// workspace, e.g. `"AGENTS.md"` or `"context/*.md"`), searched by
+    /// `load_bootstrap_files` (see `configure_bootstrap_files`).
+    bootstrap_sources: Mutex<Vec<String>>,
+}
+
+/// Configuration for `build_user_content`'s attachment pipeline (see
+/// `ContextBuilder::configure_attachments`): per-extension text-loader
+/// callbacks for documents, an optional transcription callback for audio,
+/// and a byte cap applied before any extracted text is inlined.
+struct AttachmentConfig {
+    loaders: HashMap<String, PyObject>,
+    transcriber: Option<PyObject>,
+    max_bytes: usize,
+}
+
+impl Default for AttachmentConfig {
+    fn default() -> Self {
+        AttachmentConfig {
+            loaders: HashMap::new(),
+            transcriber: None,
+            max_bytes: DEFAULT_ATTACHMENT_MAX_BYTES,
+        }
+    }
+}
+
+/// Per-backend capability flags controlling how `build_messages`/
+/// `add_tool_result` reshape their output (see
+/// `ContextBuilder::configure_capabilities`). Defaults match the
+/// OpenAI-style assembly this builder used before capabilities existed, so
+/// an unconfigured `ContextBuilder` behaves exactly as before.
+struct Capabilities {
+    system_prompt_support: bool,
+    multimodal: bool,
+    tool_role_support: bool,
+    request_level: Option<String>,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities {
+            system_prompt_support: true,
+            multimodal: true,
+            tool_role_support: true,
+            request_level: None,
+        }
+    }
+}
+
+/// Config for the windowed-history-plus-rolling-summary scheme used by
+/// `ContextBuilder::build_messages`, set once via `configure_history_budget`
+/// and shared by every session using this `ContextBuilder`. The actual
+/// rolling-summary progress lives in `HistoryState` instead, keyed per
+/// session, since it tracks one conversation's history rather than this
+/// shared config.
+struct HistoryBudget {
+    max_context_tokens: Option<usize>,
+    recent_window: usize,
+    summarizer: Option<PyObject>,
+    token_counter: Option<PyObject>,
+}
+
+impl Default for HistoryBudget {
+    fn default() -> Self {
+        HistoryBudget {
+            max_context_tokens: None,
+            recent_window: DEFAULT_RECENT_WINDOW,
+            summarizer: None,
+            token_counter: None,
+        }
+    }
+}
+
+/// One session's progress through the rolling-summary scheme: carried
+/// across `windowed_history` calls for that `session_key` so each overflow
+/// only summarizes the newly-evicted turns rather than re-summarizing the
+/// whole prefix from scratch.
+#[derive(Default)]
+struct HistoryState {
+    summarized_through: usize,
+    running_summary: Option<String>,
 }
 
 #[pymethods]
@@ -28,22 +133,123 @@ impl ContextBuilder {
     #[new]
     fn new(workspace: PathBuf) -> PyResult<Self> {
         let memory = MemoryStore::new(workspace.clone())?;
-        let skills = SkillsLoader::new(workspace.clone(), None);
+        let skills = SkillsLoader::new(workspace.clone(), None, None);
 
         Ok(ContextBuilder {
             workspace,
             memory,
             skills,
+            history_budget: Mutex::new(HistoryBudget::default()),
+            history_state: Mutex::new(HashMap::new()),
+            capabilities: Mutex::new(Capabilities::default()),
+            attachments: Mutex::new(AttachmentConfig::default()),
+            bootstrap_sources: Mutex::new(BOOTSTRAP_FILES.iter().map(|s| s.to_string()).collect()),
         })
     }
 
+    /// Configure which bootstrap files `load_bootstrap_files` searches and
+    /// in what order, replacing the built-in `AGENTS.md`/`SOUL.md`/...
+    /// list. Each entry is a path relative to the workspace, or a glob
+    /// pattern whose final path segment contains a single `*` (e.g.
+    /// `"context/*.md"`) to pull in every matching file in that directory,
+    /// sorted by filename. See `load_bootstrap_files` for how each file's
+    /// frontmatter directives (`priority`, `max_chars`, `when`, `role`)
+    /// are then applied.
+    fn configure_bootstrap_files(&self, files: Vec<String>) {
+        *self.bootstrap_sources.lock() = files;
+    }
+
+    /// Configure the attachment pipeline used by `build_user_content` for
+    /// non-image media. `loaders` maps a lowercase file extension (without
+    /// the dot, e.g. `"pdf"`) to a Python callable `(path: str) -> str` that
+    /// extracts its text; `"txt"`/`"md"` are read verbatim even without a
+    /// loader configured. `transcriber` is a callable `(path: str) -> str`
+    /// used for audio files when set; otherwise audio is inlined as an
+    /// `input_audio` content part with base64 data. Extracted/transcribed
+    /// text is truncated to `max_bytes` before being inlined so a single
+    /// large document can't blow out the prompt.
+    #[pyo3(signature = (loaders=None, transcriber=None, max_bytes=None))]
+    fn configure_attachments(
+        &self,
+        loaders: Option<HashMap<String, PyObject>>,
+        transcriber: Option<PyObject>,
+        max_bytes: Option<usize>,
+    ) {
+        let mut cfg = self.attachments.lock();
+        cfg.loaders = loaders.unwrap_or_default();
+        cfg.transcriber = transcriber;
+        cfg.max_bytes = max_bytes.unwrap_or(DEFAULT_ATTACHMENT_MAX_BYTES);
+    }
+
+    /// Configure the target backend's capabilities so `build_messages`/
+    /// `add_tool_result` can reshape their output: fold the system prompt
+    /// into the current user message when `system_prompt_support` is false,
+    /// replace image attachments with a textual placeholder when
+    /// `multimodal` is false, and render tool results as a plain user turn
+    /// instead of a `tool`-role message when `tool_role_support` is false.
+    /// `request_level` is an opaque hint stored for callers that need it;
+    /// this builder doesn't interpret it itself.
+    #[pyo3(signature = (system_prompt_support=true, multimodal=true, tool_role_support=true, request_level=None))]
+    fn configure_capabilities(
+        &self,
+        system_prompt_support: bool,
+        multimodal: bool,
+        tool_role_support: bool,
+        request_level: Option<String>,
+    ) {
+        let mut caps = self.capabilities.lock();
+        caps.system_prompt_support = system_prompt_support;
+        caps.multimodal = multimodal;
+        caps.tool_role_support = tool_role_support;
+        caps.request_level = request_level;
+    }
+
+    /// Configure token-budgeted history windowing for `build_messages`.
+    ///
+    /// When `max_context_tokens` is set, `build_messages` keeps the last
+    /// `recent_window` user/assistant turns verbatim and collapses everything
+    /// older into a single rolling summary once the assembled messages would
+    /// exceed the budget. `summarizer` is an optional Python callable
+    /// `(evicted_messages, previous_summary) -> str` used to condense the
+    /// evicted prefix; if omitted, a deterministic truncation is used instead.
+    /// `token_counter` is an optional Python callable `(message) -> int` used
+    /// to count tokens per message; if omitted, token counts fall back to
+    /// `len(content) // 4` (at least 1). Reconfiguring resets every session's in-progress
+    /// rolling summary, since the old incremental state no longer applies.
+    #[pyo3(signature = (max_context_tokens=None, recent_window=None, summarizer=None, token_counter=None))]
+    fn configure_history_budget(
+        &self,
+        max_context_tokens: Option<usize>,
+        recent_window: Option<usize>,
+        summarizer: Option<PyObject>,
+        token_counter: Option<PyObject>,
+    ) {
+        let mut budget = self.history_budget.lock();
+        budget.max_context_tokens = max_context_tokens;
+        budget.recent_window = recent_window.unwrap_or(DEFAULT_RECENT_WINDOW);
+        budget.summarizer = summarizer;
+        budget.token_counter = token_counter;
+        self.history_state.lock().clear();
+    }
+
     /// Build the system prompt from bootstrap files, memory, and skills.
-    #[pyo3(signature = (skill_names=None))]
+    ///
+    /// By default the full memory context is dumped in, same as always. If
+    /// `current_message` and `retrieval_top_k` are both given, the memory
+    /// section is instead the top-`retrieval_top_k` chunks of `MEMORY.md`/the
+    /// daily notes (see `MemoryStore::search`) ranked by relevance to
+    /// `current_message`, scoped to chunks at or above `retrieval_threshold`
+    /// (default `0.0`, which drops only negative scores). This keeps the prompt bounded as
+    /// memory grows instead of scaling with its total size.
+    #[pyo3(signature = (skill_names=None, current_message=None, retrieval_top_k=None, retrieval_threshold=None))]
     #[allow(unused_variables)]
     fn build_system_prompt(
         &self,
         py: Python<'_>,
         skill_names: Option<Vec<String>>,
+        current_message: Option<&str>,
+        retrieval_top_k: Option<usize>,
+        retrieval_threshold: Option<f32>,
     ) -> PyResult<String> {
         let mut parts = Vec::new();
 
@@ -51,13 +257,19 @@ impl ContextBuilder {
         parts.push(self.get_identity());
 
         // Bootstrap files
-        let bootstrap = self.load_bootstrap_files();
+        let bootstrap = self.load_bootstrap_files(current_message);
         if !bootstrap.is_empty() {
             parts.push(bootstrap);
         }
 
-        // Memory context
-        let memory = self.memory.get_memory_context();
+        // Memory context: retrieved top-k chunks when configured, else the
+        // full dump (unaffected default behavior).
+        let memory = match (current_message, retrieval_top_k) {
+            (Some(query), Some(top_k)) if !query.is_empty() => {
+                self.retrieve_memory_context(py, query, top_k, retrieval_threshold.unwrap_or(0.0))?
+            }
+            _ => self.memory.get_memory_context(),
+        };
         if !memory.is_empty() {
             parts.push(format!("# Memory\n\n{}", memory));
         }
@@ -88,31 +300,65 @@ impl ContextBuilder {
     }
 
     /// Build the complete message list for an LLM call.
-    #[pyo3(signature = (history, current_message, skill_names=None, media=None))]
+    ///
+    /// `session_key` identifies the conversation `history` belongs to, so
+    /// the rolling-summary state `windowed_history` carries between calls
+    /// (see `configure_history_budget`) doesn't bleed between sessions
+    /// sharing this workspace-scoped `ContextBuilder`.
+    ///
+    /// `retrieval_top_k`/`retrieval_threshold` are forwarded to
+    /// `build_system_prompt` to scope the memory section to chunks relevant
+    /// to `current_message`; leave both `None` for the full-dump default.
+    #[pyo3(signature = (session_key, history, current_message, skill_names=None, media=None, retrieval_top_k=None, retrieval_threshold=None))]
     fn build_messages(
         &self,
         py: Python<'_>,
+        session_key: &str,
         history: &Bound<'_, PyList>,
         current_message: &str,
         skill_names: Option<Vec<String>>,
         media: Option<Vec<String>>,
+        retrieval_top_k: Option<usize>,
+        retrieval_threshold: Option<f32>,
     ) -> PyResult<Py<PyList>> {
         let messages = PyList::empty(py);
 
+        let (system_prompt_support, multimodal) = {
+            let caps = self.capabilities.lock();
+            (caps.system_prompt_support, caps.multimodal)
+        };
+
         // System prompt
-        let system_prompt = self.build_system_prompt(py, skill_names)?;
-        let system_msg = PyDict::new(py);
-        system_msg.set_item("role", "system")?;
-        system_msg.set_item("content", system_prompt)?;
-        messages.append(system_msg)?;
+        let system_prompt = self.build_system_prompt(
+            py,
+            skill_names,
+            Some(current_message),
+            retrieval_top_k,
+            retrieval_threshold,
+        )?;
+        if system_prompt_support {
+            let system_msg = PyDict::new(py);
+            system_msg.set_item("role", "system")?;
+            system_msg.set_item("content", system_prompt.clone())?;
+            messages.append(system_msg)?;
+        }
 
-        // History
-        for item in history.iter() {
+        // History, windowed and summarized if a token budget is configured.
+        for item in
+            self.windowed_history(py, session_key, history, &system_prompt, current_message)?
+        {
             messages.append(item)?;
         }
 
-        // Current message (with optional image attachments)
-        let user_content = self.build_user_content(py, current_message, media)?;
+        // Current message (with optional image attachments). When the
+        // backend has no system role, the system prompt is folded in here
+        // instead of being dropped.
+        let effective_message = if system_prompt_support {
+            current_message.to_string()
+        } else {
+            format!("{}\n\n---\n\n{}", system_prompt, current_message)
+        };
+        let user_content = self.build_user_content(py, &effective_message, media, multimodal)?;
         let user_msg = PyDict::new(py);
         user_msg.set_item("role", "user")?;
         user_msg.set_item("content", user_content)?;
@@ -121,7 +367,9 @@ impl ContextBuilder {
         Ok(messages.into())
     }
 
-    /// Add a tool result to the message list.
+    /// Add a tool result to the message list. When the backend has no
+    /// `tool` role (see `configure_capabilities`), the result is rendered as
+    /// a plain user-role turn instead.
     fn add_tool_result(
         &self,
         py: Python<'_>,
@@ -130,11 +378,18 @@ impl ContextBuilder {
         tool_name: &str,
         result: &str,
     ) -> PyResult<Py<PyList>> {
+        let tool_role_support = self.capabilities.lock().tool_role_support;
+
         let msg = PyDict::new(py);
-        msg.set_item("role", "tool")?;
-        msg.set_item("tool_call_id", tool_call_id)?;
-        msg.set_item("name", tool_name)?;
-        msg.set_item("content", result)?;
+        if tool_role_support {
+            msg.set_item("role", "tool")?;
+            msg.set_item("tool_call_id", tool_call_id)?;
+            msg.set_item("name", tool_name)?;
+            msg.set_item("content", result)?;
+        } else {
+            msg.set_item("role", "user")?;
+            msg.set_item("content", format!("[Tool result: {}]\n{}", tool_name, result))?;
+        }
         messages.append(msg)?;
 
         Ok(messages.clone().unbind())
@@ -209,84 +464,507 @@ When remembering something, write to {}/memory/MEMORY.md"#,
         )
     }
 
-    fn load_bootstrap_files(&self) -> String {
+    /// Rank `MemoryStore`'s indexed chunks (MEMORY.md and daily notes,
+    /// chunked/embedded/cached by `MemoryStore::build_index`) against `query`
+    /// and join the top `top_k` at or above `min_score` into a short excerpt
+    /// section, instead of dumping every memory file in full.
+    fn retrieve_memory_context(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        top_k: usize,
+        min_score: f32,
+    ) -> PyResult<String> {
+        self.memory.build_index()?;
+        let hits = self.memory.search(py, query.to_string(), top_k, min_score, None)?;
+        let hits = hits.bind(py);
+
         let mut parts = Vec::new();
+        for item in hits.iter() {
+            let dict = item.downcast::<PyDict>()?;
+            let path: String = dict
+                .get_item("path")?
+                .map(|v| v.extract())
+                .transpose()?
+                .unwrap_or_default();
+            let snippet: String = dict
+                .get_item("snippet")?
+                .map(|v| v.extract())
+                .transpose()?
+                .unwrap_or_default();
+            parts.push(format!("### {}\n{}", path, snippet));
+        }
 
-        for filename in BOOTSTRAP_FILES {
-            let file_path = self.workspace.join(filename);
-            if file_path.exists() {
-                if let Ok(content) = fs::read_to_string(&file_path) {
-                    parts.push(format!("## {}\n\n{}", filename, content));
+        Ok(parts.join("\n\n"))
+    }
+
+    /// Load configured bootstrap files (see `configure_bootstrap_files`),
+    /// in source order, resolving any glob patterns along the way. Each
+    /// file may carry an optional frontmatter header between `---` lines
+    /// with directives: `priority` (integer, higher sorts first within the
+    /// final output; default `0`), `max_chars` (truncate the file's body to
+    /// this many characters), `when` (a regex checked against
+    /// `current_message`; the file is skipped if it doesn't match or if
+    /// `current_message` is `None`), and `role` (`system`, `developer`, or
+    /// `user`; default `system` - any other role is marked with a `(role)`
+    /// suffix on the file's heading but stays in priority order, since this
+    /// function returns one flat string rather than structured messages).
+    fn load_bootstrap_files(&self, current_message: Option<&str>) -> String {
+        let sources = self.bootstrap_sources.lock().clone();
+        let mut entries: Vec<BootstrapEntry> = Vec::new();
+
+        for (order, file_path) in self.resolve_bootstrap_sources(&sources).into_iter().enumerate() {
+            let Ok(raw) = fs::read_to_string(&file_path) else {
+                continue;
+            };
+            let name = file_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let directives = parse_bootstrap_frontmatter(&raw);
+            let body = strip_frontmatter(&raw);
+
+            if let Some(expr) = directives.get("when") {
+                let matched = current_message
+                    .and_then(|msg| Regex::new(expr).ok().map(|re| re.is_match(msg)))
+                    .unwrap_or(false);
+                if !matched {
+                    continue;
                 }
             }
+
+            let priority = directives
+                .get("priority")
+                .and_then(|v| v.parse::<i64>().ok())
+                .unwrap_or(0);
+            let content = match directives.get("max_chars").and_then(|v| v.parse::<usize>().ok()) {
+                Some(max_chars) => truncate_to_chars(body.trim(), max_chars),
+                None => body.trim().to_string(),
+            };
+            let role = directives
+                .get("role")
+                .cloned()
+                .unwrap_or_else(|| "system".to_string());
+
+            entries.push(BootstrapEntry {
+                priority,
+                order,
+                role,
+                name,
+                content,
+            });
         }
 
-        if parts.is_empty() {
-            String::new()
-        } else {
-            parts.join("\n\n")
+        // Higher priority first; ties keep source order.
+        entries.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.order.cmp(&b.order)));
+
+        let parts: Vec<String> = entries
+            .iter()
+            .map(|e| {
+                if e.role == "system" {
+                    format!("## {}\n\n{}", e.name, e.content)
+                } else {
+                    format!("## {} ({})\n\n{}", e.name, e.role, e.content)
+                }
+            })
+            .collect();
+
+        parts.join("\n\n")
+    }
+
+    /// Expand `sources` (literal workspace-relative paths, or glob patterns
+    /// whose final path segment contains a single `*`) into existing file
+    /// paths, in order, with glob matches sorted by filename. A path
+    /// appearing more than once (whether named literally or matched by more
+    /// than one pattern) is only included the first time.
+    fn resolve_bootstrap_sources(&self, sources: &[String]) -> Vec<PathBuf> {
+        let mut resolved = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+
+        for pattern in sources {
+            if let Some(star) = pattern.find('*') {
+                let (dir_part, file_glob) = match pattern[..star].rfind('/') {
+                    Some(slash) => (&pattern[..slash], &pattern[slash + 1..]),
+                    None => ("", pattern.as_str()),
+                };
+                let dir = self.workspace.join(dir_part);
+                let Ok(entries) = fs::read_dir(&dir) else {
+                    continue;
+                };
+                let mut matches: Vec<PathBuf> = entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| {
+                        p.is_file()
+                            && p.file_name()
+                                .map(|n| glob_match(file_glob, &n.to_string_lossy()))
+                                .unwrap_or(false)
+                    })
+                    .collect();
+                matches.sort();
+                for path in matches {
+                    if seen.insert(path.clone()) {
+                        resolved.push(path);
+                    }
+                }
+            } else {
+                let path = self.workspace.join(pattern);
+                if path.is_file() && seen.insert(path.clone()) {
+                    resolved.push(path);
+                }
+            }
         }
+
+        resolved
     }
 
+    /// Build the `user` message content: plain `text` when there's no
+    /// media, otherwise a pluggable attachment pipeline keyed by
+    /// `guess_mime_type`. Images (and, with no transcriber configured,
+    /// audio) become base64 content parts when `multimodal` is true, or a
+    /// textual placeholder otherwise; documents are inlined as labeled text
+    /// via a configured loader (`configure_attachments`) or, for
+    /// `txt`/`md`, read verbatim; audio with a transcriber configured is
+    /// inlined as its transcript regardless of `multimodal`, since a
+    /// transcript is plain text either way.
     fn build_user_content(
         &self,
         py: Python<'_>,
         text: &str,
         media: Option<Vec<String>>,
+        multimodal: bool,
     ) -> PyResult<PyObject> {
         let media = match media {
             Some(m) if !m.is_empty() => m,
             _ => return Ok(text.into_pyobject(py)?.into_any().unbind()),
         };
 
-        let mut images = Vec::new();
+        let attachments = self.attachments.lock();
+        let mut parts = Vec::new();
+        let mut inline_texts = Vec::new();
 
         for path in &media {
             let p = PathBuf::from(path);
             if !p.is_file() {
                 continue;
             }
-
             let mime = guess_mime_type(path);
-            if !mime.starts_with("image/") {
+            let ext = extension_of(path);
+            let label = attachment_label(&p, path);
+
+            if mime.starts_with("image/") {
+                if !multimodal {
+                    inline_texts.push(format!("[image attached: {} ({})]", label, mime));
+                    continue;
+                }
+                if let Ok(bytes) = fs::read(&p) {
+                    let b64 = BASE64.encode(&bytes);
+                    let image_dict = PyDict::new(py);
+                    image_dict.set_item("type", "image_url")?;
+                    let url_dict = PyDict::new(py);
+                    url_dict.set_item("url", format!("data:{};base64,{}", mime, b64))?;
+                    image_dict.set_item("image_url", url_dict)?;
+                    parts.push(image_dict);
+                }
                 continue;
             }
 
-            if let Ok(bytes) = fs::read(&p) {
-                let b64 = BASE64.encode(&bytes);
-                let image_dict = PyDict::new(py);
-                image_dict.set_item("type", "image_url")?;
-
-                let url_dict = PyDict::new(py);
-                url_dict.set_item("url", format!("data:{};base64,{}", mime, b64))?;
-                image_dict.set_item("image_url", url_dict)?;
+            if mime.starts_with("audio/") {
+                if let Some(cb) = &attachments.transcriber {
+                    if let Ok(transcript) = cb.call1(py, (path.as_str(),))?.extract::<String>(py) {
+                        inline_texts.push(format!(
+                            "[Transcript of {}]\n{}",
+                            label,
+                            truncate_to_bytes(&transcript, attachments.max_bytes)
+                        ));
+                    }
+                    continue;
+                }
+                if !multimodal {
+                    inline_texts.push(format!("[audio attached: {} ({})]", label, mime));
+                    continue;
+                }
+                if let Ok(bytes) = fs::read(&p) {
+                    let b64 = BASE64.encode(&bytes);
+                    let audio_dict = PyDict::new(py);
+                    audio_dict.set_item("type", "input_audio")?;
+                    let data_dict = PyDict::new(py);
+                    data_dict.set_item("data", b64)?;
+                    data_dict.set_item("format", ext)?;
+                    audio_dict.set_item("input_audio", data_dict)?;
+                    parts.push(audio_dict);
+                }
+                continue;
+            }
 
-                images.push(image_dict);
+            // Documents: an injected loader wins; txt/md are read verbatim
+            // as a sane default; anything else (e.g. pdf/html with no
+            // loader configured) is skipped since we can't parse it
+            // ourselves without one.
+            let extracted = if let Some(cb) = attachments.loaders.get(&ext) {
+                cb.call1(py, (path.as_str(),))?.extract::<String>(py).ok()
+            } else if ext == "txt" || ext == "md" {
+                fs::read_to_string(&p).ok()
+            } else {
+                None
+            };
+            if let Some(doc_text) = extracted {
+                inline_texts.push(format!(
+                    "[Attachment: {}]\n{}",
+                    label,
+                    truncate_to_bytes(&doc_text, attachments.max_bytes)
+                ));
             }
         }
+        drop(attachments);
 
-        if images.is_empty() {
+        if parts.is_empty() && inline_texts.is_empty() {
             return Ok(text.into_pyobject(py)?.into_any().unbind());
         }
 
-        // Build content array: images + text
+        let mut full_text = text.to_string();
+        if !inline_texts.is_empty() {
+            full_text = format!("{}\n\n{}", full_text, inline_texts.join("\n\n"));
+        }
+
+        if parts.is_empty() {
+            return Ok(full_text.into_pyobject(py)?.into_any().unbind());
+        }
+
+        // Build content array: media parts + text
         let content = PyList::empty(py);
-        for img in images {
-            content.append(img)?;
+        for part in parts {
+            content.append(part)?;
         }
 
         let text_dict = PyDict::new(py);
         text_dict.set_item("type", "text")?;
-        text_dict.set_item("text", text)?;
+        text_dict.set_item("text", full_text)?;
         content.append(text_dict)?;
 
         Ok(content.into())
     }
+
+    /// Apply the configured history budget: if no budget is set, or the
+    /// assembled messages already fit, return `history` unchanged. Otherwise
+    /// keep the last `recent_window` user turns verbatim and splice in a
+    /// synthetic system message carrying `session_key`'s rolling summary of
+    /// everything evicted so far.
+    fn windowed_history(
+        &self,
+        py: Python<'_>,
+        session_key: &str,
+        history: &Bound<'_, PyList>,
+        system_prompt: &str,
+        current_message: &str,
+    ) -> PyResult<Vec<Py<PyAny>>> {
+        let budget = self.history_budget.lock();
+        let Some(max_tokens) = budget.max_context_tokens else {
+            return Ok(history.iter().map(|item| item.unbind()).collect());
+        };
+
+        let n = history.len();
+        let mut total =
+            estimate_tokens_fallback(system_prompt) + estimate_tokens_fallback(current_message);
+        for item in history.iter() {
+            total += count_tokens(py, budget.token_counter.as_ref(), &item)?;
+        }
+        if total <= max_tokens {
+            return Ok(history.iter().map(|item| item.unbind()).collect());
+        }
+
+        // Walk back from the end, keeping the last `recent_window` user turns.
+        let mut split = n;
+        let mut turns_seen = 0usize;
+        let mut idx = n;
+        while idx > 0 {
+            idx -= 1;
+            let item = history.get_item(idx)?;
+            if history_item_role(&item) == "user" {
+                turns_seen += 1;
+            }
+            split = idx;
+            if turns_seen >= budget.recent_window {
+                break;
+            }
+        }
+
+        let mut state = self.history_state.lock();
+        let state = state.entry(session_key.to_string()).or_default();
+
+        if split > state.summarized_through {
+            let evicted = PyList::empty(py);
+            for i in state.summarized_through..split {
+                evicted.append(history.get_item(i)?)?;
+            }
+            let previous = state.running_summary.clone();
+            let summary = match &budget.summarizer {
+                Some(cb) => cb
+                    .call1(py, (evicted.clone(), previous.clone()))?
+                    .extract::<String>(py)?,
+                None => default_history_summary(&evicted, previous.as_deref())?,
+            };
+            state.running_summary = Some(summary);
+            state.summarized_through = split;
+        }
+
+        let mut result = Vec::with_capacity(n - split + 1);
+        if let Some(summary) = &state.running_summary {
+            let note = PyDict::new(py);
+            note.set_item("role", "system")?;
+            note.set_item("content", format!("# Earlier conversation summary\n\n{}", summary))?;
+            result.push(note.into_any().unbind());
+        }
+        for i in split..n {
+            result.push(history.get_item(i)?.unbind());
+        }
+
+        Ok(result)
+    }
+}
+
+/// Extract the `role` field of a history item, defaulting to empty string.
+fn history_item_role(item: &Bound<'_, PyAny>) -> String {
+    item.get_item("role")
+        .ok()
+        .and_then(|v| v.extract::<String>().ok())
+        .unwrap_or_default()
+}
+
+/// Extract the `content` field of a history item, defaulting to empty string.
+fn history_item_content(item: &Bound<'_, PyAny>) -> String {
+    item.get_item("content")
+        .ok()
+        .and_then(|v| v.extract::<String>().ok())
+        .unwrap_or_default()
+}
+
+/// Crude fallback token estimate (~4 chars per token) used when no
+/// `token_counter` callback is configured.
+fn estimate_tokens_fallback(text: &str) -> usize {
+    (text.len() / 4).max(1)
+}
+
+/// Count tokens for a single history item, using the injected callback if
+/// present, else the `estimate_tokens_fallback` heuristic on its content.
+fn count_tokens(
+    py: Python<'_>,
+    token_counter: Option<&PyObject>,
+    item: &Bound<'_, PyAny>,
+) -> PyResult<usize> {
+    if let Some(cb) = token_counter {
+        return cb.call1(py, (item,))?.extract::<usize>(py);
+    }
+    Ok(estimate_tokens_fallback(&history_item_content(item)))
+}
+
+/// Deterministic fallback summarizer used when no `summarizer` callback is
+/// configured: joins the evicted turns as `role: content` lines onto the
+/// previous running summary, truncated to `MAX_SUMMARY_CHARS`.
+fn default_history_summary(evicted: &Bound<'_, PyList>, previous: Option<&str>) -> PyResult<String> {
+    let mut lines = Vec::new();
+    if let Some(prev) = previous {
+        lines.push(prev.to_string());
+    }
+    for item in evicted.iter() {
+        let role = history_item_role(&item);
+        let content = history_item_content(&item);
+        lines.push(format!("{}: {}", role, content));
+    }
+    let joined = lines.join("\n");
+    if joined.len() <= MAX_SUMMARY_CHARS {
+        Ok(joined)
+    } else {
+        let truncated: String = joined.chars().take(MAX_SUMMARY_CHARS).collect();
+        Ok(format!("{}...", truncated))
+    }
+}
+
+/// One included bootstrap file after its frontmatter directives are
+/// applied, carried through sorting in `load_bootstrap_files`.
+struct BootstrapEntry {
+    priority: i64,
+    /// Source order, used as the sort tie-break so same-priority files
+    /// keep the order they were configured in.
+    order: usize,
+    role: String,
+    name: String,
+    content: String,
+}
+
+/// Parse a bootstrap file's optional frontmatter (a `key: value` line per
+/// directive, between `---` delimiters at the top of the file) into a map,
+/// mirroring `skills.rs`'s `get_skill_metadata` parsing.
+fn parse_bootstrap_frontmatter(content: &str) -> HashMap<String, String> {
+    let mut directives = HashMap::new();
+    if !content.starts_with("---") {
+        return directives;
+    }
+    let Ok(re) = Regex::new(r"(?s)^---\n(.*?)\n---") else {
+        return directives;
+    };
+    let Some(caps) = re.captures(content) else {
+        return directives;
+    };
+    let Some(frontmatter) = caps.get(1) else {
+        return directives;
+    };
+
+    for line in frontmatter.as_str().lines() {
+        if let Some(idx) = line.find(':') {
+            let key = line[..idx].trim().to_string();
+            let value = line[idx + 1..]
+                .trim()
+                .trim_matches('"')
+                .trim_matches('\'')
+                .to_string();
+            directives.insert(key, value);
+        }
+    }
+
+    directives
+}
+
+/// Strip a leading `---\n...\n---\n` frontmatter block, if present.
+fn strip_frontmatter(content: &str) -> String {
+    if content.starts_with("---") {
+        if let Ok(re) = Regex::new(r"(?s)^---\n.*?\n---\n") {
+            if let Some(m) = re.find(content) {
+                return content[m.end()..].to_string();
+            }
+        }
+    }
+    content.to_string()
+}
+
+/// Match `name` against `pattern`, where `pattern` contains at most one `*`
+/// wildcard (e.g. `"*.md"`); a pattern with no `*` requires an exact match.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    match pattern.find('*') {
+        None => pattern == name,
+        Some(idx) => {
+            let prefix = &pattern[..idx];
+            let suffix = &pattern[idx + 1..];
+            name.len() >= prefix.len() + suffix.len()
+                && name.starts_with(prefix)
+                && name.ends_with(suffix)
+        }
+    }
+}
+
+/// Truncate `text` to at most `max_chars` characters, appending a trailing
+/// `"..."` marker when truncated.
+fn truncate_to_chars(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(max_chars).collect();
+    format!("{}...", truncated)
 }
 
 /// Guess MIME type from file extension.
 fn guess_mime_type(path: &str) -> String {
-    let ext = path.rsplit('.').next().unwrap_or("").to_lowercase();
+    let ext = extension_of(path);
     match ext.as_str() {
         "jpg" | "jpeg" => "image/jpeg".to_string(),
         "png" => "image/png".to_string(),
@@ -295,6 +973,43 @@ fn guess_mime_type(path: &str) -> String {
         "svg" => "image/svg+xml".to_string(),
         "bmp" => "image/bmp".to_string(),
         "ico" => "image/x-icon".to_string(),
+        "pdf" => "application/pdf".to_string(),
+        "txt" => "text/plain".to_string(),
+        "md" => "text/markdown".to_string(),
+        "html" | "htm" => "text/html".to_string(),
+        "wav" => "audio/wav".to_string(),
+        "mp3" => "audio/mpeg".to_string(),
+        "m4a" => "audio/mp4".to_string(),
         _ => "application/octet-stream".to_string(),
     }
 }
+
+/// Lowercased file extension (without the dot), or empty string if `path`
+/// has none.
+fn extension_of(path: &str) -> String {
+    std::path::Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Display label for an attachment in inlined text: the file's basename, or
+/// the path as given if it has none.
+fn attachment_label(p: &std::path::Path, path: &str) -> String {
+    p.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// Truncate `text` to at most `max_bytes` bytes, on a UTF-8 char boundary,
+/// appending a trailing `"..."` marker when truncated.
+fn truncate_to_bytes(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+    let mut end = max_bytes;
+    while end > 0 && !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}...", &text[..end])
+}
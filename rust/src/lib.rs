@@ -19,8 +19,9 @@ use messages::{InboundMessage, OutboundMessage};
 use session::{Session, SessionManager};
 use skills::SkillsLoader;
 use tools::{
-    EditFileTool, ExecTool, ListDirTool, ReadFileTool, ToolRegistry, WebFetchTool, WebSearchTool,
-    WriteFileTool,
+    CommandPolicy, CopyFileTool, DeleteFileTool, EditFileTool, ExecStream, ExecTool, JobInfo,
+    JobManager, JobState, ListDirTool, MoveFileTool, ReadFileTool, SearchFileTool, ToolRegistry,
+    WebFetchTool, WebSearchTool, WriteFileTool,
 };
 
 /// Rust implementation of debot core modules.
@@ -37,10 +38,21 @@ fn debot_rust(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<WriteFileTool>()?;
     m.add_class::<EditFileTool>()?;
     m.add_class::<ListDirTool>()?;
+    m.add_class::<SearchFileTool>()?;
+    m.add_class::<CopyFileTool>()?;
+    m.add_class::<MoveFileTool>()?;
+    m.add_class::<DeleteFileTool>()?;
     m.add_class::<ExecTool>()?;
+    m.add_class::<ExecStream>()?;
+    m.add_class::<CommandPolicy>()?;
     m.add_class::<WebSearchTool>()?;
     m.add_class::<WebFetchTool>()?;
 
+    // Background job manager
+    m.add_class::<JobManager>()?;
+    m.add_class::<JobInfo>()?;
+    m.add_class::<JobState>()?;
+
     // Session classes
     m.add_class::<Session>()?;
     m.add_class::<SessionManager>()?;
@@ -6,11 +6,11 @@ use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
-use uuid::Uuid;
+use std::path::{Path, PathBuf};
 
 /// Memory system for the agent.
 ///
@@ -21,6 +21,7 @@ pub struct MemoryStore {
     memory_dir: PathBuf,
     memory_file: PathBuf,
     index_file: PathBuf,
+    provider: Box<dyn EmbeddingProvider>,
 }
 
 #[pymethods]
@@ -45,6 +46,7 @@ impl MemoryStore {
             memory_dir,
             memory_file,
             index_file,
+            provider: select_provider(),
         })
     }
 
@@ -174,64 +176,121 @@ impl MemoryStore {
         Ok(result.into())
     }
 
-    /// Build a simple, local vector index for all markdown memory files.
-    /// This uses a deterministic local embedding (SHA256-based) so no external API is required.
+    /// Incrementally rebuild the vector index for all markdown memory
+    /// files. Files whose content hash is unchanged since the last build
+    /// keep their cached chunks and are not re-embedded; new or modified
+    /// files are re-chunked and embedded; files that no longer exist are
+    /// dropped. Switching [`EmbeddingProvider`] forces a full rebuild.
     pub fn build_index(&self) -> PyResult<usize> {
-        let mut entries: Vec<IndexEntry> = Vec::new();
+        const CHUNK_BUDGET: usize = 800;
+        const CHUNK_OVERLAP: usize = 100;
 
         if !self.memory_dir.exists() {
             return Ok(0);
         }
 
+        let existing = self.load_raw_index_opt();
+        let reuse_cache = existing
+            .as_ref()
+            .map(|idx| {
+                idx.provider == self.provider.name() && idx.dimensions == self.provider.dimensions()
+            })
+            .unwrap_or(false);
+        let mut cached_files: HashMap<String, FileIndex> = if reuse_cache {
+            existing.unwrap().files
+        } else {
+            HashMap::new()
+        };
+
+        let mut new_files: HashMap<String, FileIndex> = HashMap::new();
+        let mut pending: Vec<(String, Unit)> = Vec::new();
+
         if let Ok(entries_iter) = fs::read_dir(&self.memory_dir) {
             for entry in entries_iter.flatten() {
                 let path = entry.path();
                 if !path.is_file() {
                     continue;
                 }
-                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                    if name.ends_with(".md") {
-                        if let Ok(text) = fs::read_to_string(&path) {
-                            // Chunk by roughly 800 characters with 100 char overlap
-                            let chunk_size = 800;
-                            let overlap = 100;
-                            let mut start = 0usize;
-                            let len = text.len();
-                            while start < len {
-                                let end = if start + chunk_size > len {
-                                    len
-                                } else {
-                                    start + chunk_size
-                                };
-                                let chunk = &text[start..end];
-                                let vec = embed_text(chunk);
-                                let id = Uuid::new_v4().to_string();
-                                let entry = IndexEntry {
-                                    id,
-                                    path: path
-                                        .strip_prefix(&self.workspace)
-                                        .unwrap_or(&path)
-                                        .to_string_lossy()
-                                        .to_string(),
-                                    start_line: 0,
-                                    end_line: 0,
-                                    text: chunk.to_string(),
-                                    vector: vec,
-                                };
-                                entries.push(entry);
-                                if end == len {
-                                    break;
-                                }
-                                start = end.saturating_sub(overlap);
-                            }
-                        }
+                let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                if !name.ends_with(".md") {
+                    continue;
+                }
+                let Ok(text) = fs::read_to_string(&path) else {
+                    continue;
+                };
+
+                let rel_path = path
+                    .strip_prefix(&self.workspace)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .to_string();
+                let content_hash = sha256_hex(&text);
+
+                if let Some(cached) = cached_files.remove(&rel_path) {
+                    if cached.content_hash == content_hash {
+                        new_files.insert(rel_path, cached);
+                        continue;
                     }
                 }
+
+                let mtime = file_mtime(&path);
+                let units = split_into_units(&text);
+                let packed = pack_units(&units, CHUNK_BUDGET, CHUNK_OVERLAP);
+                new_files.insert(
+                    rel_path.clone(),
+                    FileIndex {
+                        content_hash,
+                        mtime,
+                        entries: Vec::new(),
+                    },
+                );
+                for unit in packed {
+                    pending.push((rel_path.clone(), unit));
+                }
             }
         }
 
-        // Serialize index to file
-        let json = serde_json::to_string_pretty(&entries).map_err(|e| {
+        let texts: Vec<&str> = pending.iter().map(|(_, u)| u.text.as_str()).collect();
+        let vectors = embed_in_batches(self.provider.as_ref(), &texts).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Failed to embed memory chunks: {}",
+                e
+            ))
+        })?;
+
+        for ((path, unit), vector) in pending.into_iter().zip(vectors) {
+            let id = deterministic_chunk_id(&path, unit.start_line, unit.end_line);
+            let file_entry = new_files
+                .get_mut(&path)
+                .expect("file entry reserved before embedding");
+            file_entry.entries.push(IndexEntry {
+                id,
+                path: path.clone(),
+                start_line: unit.start_line,
+                end_line: unit.end_line,
+                text: unit.text,
+                vector,
+            });
+        }
+
+        let dimensions = new_files
+            .values()
+            .flat_map(|f| f.entries.first())
+            .next()
+            .map(|e| e.vector.len())
+            .unwrap_or_else(|| self.provider.dimensions());
+
+        let total: usize = new_files.values().map(|f| f.entries.len()).sum();
+
+        let index = IndexFile {
+            provider: self.provider.name().to_string(),
+            dimensions,
+            files: new_files,
+        };
+
+        let json = serde_json::to_string_pretty(&index).map_err(|e| {
             PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
                 "Failed to serialize index: {}",
                 e
@@ -251,19 +310,64 @@ impl MemoryStore {
             ))
         })?;
 
-        Ok(entries.len())
+        Ok(total)
+    }
+
+    /// Evict a single file's cached chunks so the next `build_index` call
+    /// re-splits and re-embeds it, without touching any other file's
+    /// cache. `path` may be absolute or already relative to the workspace.
+    pub fn invalidate(&self, path: String) -> PyResult<()> {
+        let Some(mut index) = self.load_raw_index_opt() else {
+            return Ok(());
+        };
+
+        let rel_path = PathBuf::from(&path)
+            .strip_prefix(&self.workspace)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or(path);
+
+        if index.files.remove(&rel_path).is_some() {
+            let json = serde_json::to_string_pretty(&index).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Failed to serialize index: {}",
+                    e
+                ))
+            })?;
+            fs::write(&self.index_file, json).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to write index file: {}",
+                    e
+                ))
+            })?;
+        }
+
+        Ok(())
     }
 
-    /// Search the local index for semantically similar chunks to `query`.
+    /// Search the local index for chunks matching `query`.
     /// Returns a list of dict-like tuples: (path, snippet, score)
-    #[pyo3(signature = (query, max_results=5, min_score=0.0))]
+    ///
+    /// `mode` selects the ranking strategy: `"vector"` (cosine similarity
+    /// over embeddings, the default), `"keyword"` (BM25 over chunk text),
+    /// or `"hybrid"` (both lists fused by Reciprocal Rank Fusion). Hybrid
+    /// mode lets search stay useful even when only the offline hash
+    /// embedding is available, since BM25 doesn't depend on embedding
+    /// quality at all.
+    ///
+    /// If the stored index was built with a different embedding provider or
+    /// dimensionality than this store currently uses, it is rebuilt first
+    /// rather than comparing incompatible vectors.
+    #[pyo3(signature = (query, max_results=5, min_score=0.0, mode=None))]
     pub fn search(
         &self,
         py: Python<'_>,
         query: String,
         max_results: usize,
         min_score: f32,
+        mode: Option<String>,
     ) -> PyResult<Py<PyList>> {
+        let mode = mode.unwrap_or_else(|| "vector".to_string());
+
         #[allow(unused_mut)]
         let mut result = PyList::empty(py);
 
@@ -271,27 +375,62 @@ impl MemoryStore {
             return Ok(result.into());
         }
 
-        let json = fs::read_to_string(&self.index_file).map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                "Failed to read index file: {}",
-                e
-            ))
-        })?;
+        let mut index = self.load_index()?;
 
-        let entries: Vec<IndexEntry> = serde_json::from_str(&json).map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to parse index: {}", e))
-        })?;
+        if index.provider != self.provider.name() || index.dimensions != self.provider.dimensions()
+        {
+            self.build_index()?;
+            index = self.load_index()?;
+        }
 
-        let qvec = embed_text(&query);
+        let needs_vector = mode != "keyword";
+        let vector_scores: Vec<f32> = if needs_vector {
+            let qvec = embed_in_batches(self.provider.as_ref(), &[query.as_str()])
+                .map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Failed to embed query: {}",
+                        e
+                    ))
+                })?
+                .into_iter()
+                .next()
+                .unwrap_or_default();
+            index
+                .entries
+                .iter()
+                .map(|e| cosine_similarity(&qvec, &e.vector))
+                .collect()
+        } else {
+            Vec::new()
+        };
 
-        let mut scored: Vec<(f32, &IndexEntry)> = entries
-            .iter()
-            .map(|e| {
-                let score = cosine_similarity(&qvec, &e.vector);
-                (score, e)
-            })
-            .collect();
+        let query_terms = tokenize(&query);
+        let bm25_scores_vec: Vec<f32> = if mode != "vector" {
+            bm25_scores(&index.entries, &query_terms)
+        } else {
+            Vec::new()
+        };
+
+        let scored: Vec<(f32, &IndexEntry)> = match mode.as_str() {
+            "keyword" => index
+                .entries
+                .iter()
+                .zip(bm25_scores_vec.iter())
+                .map(|(e, s)| (*s, e))
+                .collect(),
+            "hybrid" => {
+                let fused = reciprocal_rank_fusion(&vector_scores, &bm25_scores_vec);
+                index.entries.iter().zip(fused).map(|(e, s)| (s, e)).collect()
+            }
+            _ => index
+                .entries
+                .iter()
+                .zip(vector_scores.iter())
+                .map(|(e, s)| (*s, e))
+                .collect(),
+        };
 
+        let mut scored = scored;
         scored.retain(|(s, _)| *s >= min_score);
         scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
 
@@ -300,6 +439,8 @@ impl MemoryStore {
             dict.set_item("path", entry.path.clone())?;
             dict.set_item("snippet", entry.text.clone())?;
             dict.set_item("score", score)?;
+            dict.set_item("start_line", entry.start_line)?;
+            dict.set_item("end_line", entry.end_line)?;
             result.append(dict)?;
         }
 
@@ -346,6 +487,243 @@ impl MemoryStore {
     fn memory_file(&self) -> String {
         self.memory_file.to_string_lossy().to_string()
     }
+
+    /// Name of the embedding provider currently in use (`"openai"`,
+    /// `"ollama"`, or `"hash"`).
+    #[getter]
+    fn provider_name(&self) -> String {
+        self.provider.name().to_string()
+    }
+
+    /// Distill the last `days` of daily notes into `MEMORY.md`. Chunks
+    /// whose cosine similarity is at or above
+    /// [`CONSOLIDATION_SIMILARITY_THRESHOLD`] are treated as near-duplicate
+    /// and merged into one cluster; one representative per cluster is
+    /// appended to `MEMORY.md`, skipping clusters already reflected there.
+    /// Every chunk considered (novel or duplicate) is marked in its source
+    /// daily file with a `<!-- consolidated -->` comment so it's clear
+    /// what's already been promoted. Returns the number of facts appended.
+    #[pyo3(signature = (days=7))]
+    pub fn consolidate(&self, days: i64) -> PyResult<usize> {
+        use chrono::{Duration, Local};
+
+        let today = Local::now().date_naive();
+        let mut file_units: Vec<(PathBuf, Vec<Unit>)> = Vec::new();
+
+        for i in 0..days {
+            let date = today - Duration::days(i);
+            let path = self
+                .memory_dir
+                .join(format!("{}.md", date.format("%Y-%m-%d")));
+            if !path.exists() {
+                continue;
+            }
+            let Ok(text) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let units = split_into_units(&text);
+            if !units.is_empty() {
+                file_units.push((path, units));
+            }
+        }
+
+        if file_units.is_empty() {
+            return Ok(0);
+        }
+
+        let mut all_texts: Vec<&str> = Vec::new();
+        for (_, units) in &file_units {
+            for unit in units {
+                all_texts.push(unit.text.as_str());
+            }
+        }
+        let vectors = embed_in_batches(self.provider.as_ref(), &all_texts).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Failed to embed daily notes for consolidation: {}",
+                e
+            ))
+        })?;
+
+        struct Cluster<'a> {
+            vector: Vec<f32>,
+            text: String,
+            sources: Vec<(&'a PathBuf, usize, usize)>,
+        }
+
+        let mut clusters: Vec<Cluster> = Vec::new();
+        let mut vec_idx = 0;
+        for (path, units) in &file_units {
+            for unit in units {
+                let vector = vectors[vec_idx].clone();
+                vec_idx += 1;
+                let existing = clusters.iter_mut().find(|c| {
+                    cosine_similarity(&c.vector, &vector) >= CONSOLIDATION_SIMILARITY_THRESHOLD
+                });
+                match existing {
+                    Some(cluster) => cluster.sources.push((path, unit.start_line, unit.end_line)),
+                    None => clusters.push(Cluster {
+                        vector,
+                        text: unit.text.clone(),
+                        sources: vec![(path, unit.start_line, unit.end_line)],
+                    }),
+                }
+            }
+        }
+
+        // Skip clusters already reflected in MEMORY.md, so re-running
+        // `consolidate` doesn't pile up the same fact twice.
+        let existing_units = split_into_units(&self.read_long_term());
+        let existing_vectors = if existing_units.is_empty() {
+            Vec::new()
+        } else {
+            let texts: Vec<&str> = existing_units.iter().map(|u| u.text.as_str()).collect();
+            embed_in_batches(self.provider.as_ref(), &texts).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Failed to embed existing long-term memory: {}",
+                    e
+                ))
+            })?
+        };
+
+        let mut appended = String::new();
+        let mut novel_count = 0;
+        for cluster in &clusters {
+            let already_known = existing_vectors.iter().any(|v| {
+                cosine_similarity(v, &cluster.vector) >= CONSOLIDATION_SIMILARITY_THRESHOLD
+            });
+            if already_known {
+                continue;
+            }
+
+            let refs: Vec<String> = cluster
+                .sources
+                .iter()
+                .map(|(path, start, end)| {
+                    let name = path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    format!("{}:{}-{}", name, start, end)
+                })
+                .collect();
+            appended.push_str(&format!("- {} ({})\n", cluster.text.trim(), refs.join(", ")));
+            novel_count += 1;
+        }
+
+        if novel_count > 0 {
+            let header = format!("\n## Consolidated {}\n", today.format("%Y-%m-%d"));
+            let new_long_term = format!("{}{}{}", self.read_long_term(), header, appended);
+            self.write_long_term(new_long_term)?;
+        }
+
+        for (path, units) in &file_units {
+            mark_consolidated(path, units).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to mark consolidated lines in {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+        }
+
+        Ok(novel_count)
+    }
+
+    /// Archive daily note files older than `max_age_days` into
+    /// `memory/archive/`, removing them from future `build_index` scans
+    /// (which only look directly inside `memory/`) without deleting their
+    /// content outright. Run `consolidate` first so anything worth keeping
+    /// has already been promoted to `MEMORY.md`. Returns the number of
+    /// files archived.
+    #[pyo3(signature = (max_age_days=30))]
+    pub fn prune(&self, max_age_days: i64) -> PyResult<usize> {
+        use chrono::{Local, NaiveDate};
+
+        let cutoff = Local::now().date_naive() - chrono::Duration::days(max_age_days);
+        let archive_dir = self.memory_dir.join("archive");
+        let mut archived = 0;
+
+        let Ok(entries) = fs::read_dir(&self.memory_dir) else {
+            return Ok(0);
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if name.len() != 13 || !name.ends_with(".md") {
+                continue;
+            }
+            let Ok(date) = NaiveDate::parse_from_str(&name[..10], "%Y-%m-%d") else {
+                continue;
+            };
+            if date >= cutoff {
+                continue;
+            }
+
+            fs::create_dir_all(&archive_dir).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to create archive directory: {}",
+                    e
+                ))
+            })?;
+            fs::rename(&path, archive_dir.join(name)).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to archive {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+
+            let rel_path = path
+                .strip_prefix(&self.workspace)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string();
+            self.invalidate(rel_path)?;
+            archived += 1;
+        }
+
+        Ok(archived)
+    }
+}
+
+impl MemoryStore {
+    /// Load the on-disk index, flattening its per-file chunk caches into a
+    /// single entry list for searching.
+    fn load_index(&self) -> PyResult<SearchIndex> {
+        let raw = self.load_raw_index()?;
+        let entries = raw.files.into_values().flat_map(|f| f.entries).collect();
+        Ok(SearchIndex {
+            provider: raw.provider,
+            dimensions: raw.dimensions,
+            entries,
+        })
+    }
+
+    fn load_raw_index(&self) -> PyResult<IndexFile> {
+        let json = fs::read_to_string(&self.index_file).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to read index file: {}",
+                e
+            ))
+        })?;
+        serde_json::from_str(&json).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to parse index: {}", e))
+        })
+    }
+
+    /// Like [`Self::load_raw_index`], but `None` instead of an error when
+    /// the index file is missing or unparseable - used by `build_index` to
+    /// decide what can be reused versus rebuilt from scratch.
+    fn load_raw_index_opt(&self) -> Option<IndexFile> {
+        let json = fs::read_to_string(&self.index_file).ok()?;
+        serde_json::from_str(&json).ok()
+    }
 }
 
 /// Get today's date in YYYY-MM-DD format.
@@ -353,7 +731,255 @@ fn today_date() -> String {
     chrono::Local::now().format("%Y-%m-%d").to_string()
 }
 
+/// Cosine-similarity threshold above which two daily-note chunks are
+/// considered near-duplicates during [`MemoryStore::consolidate`].
+const CONSOLIDATION_SIMILARITY_THRESHOLD: f32 = 0.92;
+
+/// Insert a `<!-- consolidated -->` comment immediately after each of
+/// `units` in its source file, so a later `consolidate` run (or a human
+/// skimming the file) can see what's already been distilled. Units are
+/// processed bottom-to-top so earlier insertions don't shift the line
+/// numbers of units still to be marked.
+fn mark_consolidated(path: &Path, units: &[Unit]) -> std::io::Result<()> {
+    let text = fs::read_to_string(path)?;
+    let mut lines: Vec<String> = text.lines().map(|s| s.to_string()).collect();
+
+    let mut sorted: Vec<&Unit> = units.iter().collect();
+    sorted.sort_by(|a, b| b.end_line.cmp(&a.end_line));
+
+    for unit in sorted {
+        let insert_at = unit.end_line.min(lines.len());
+        if lines
+            .get(insert_at)
+            .map(|l| l.trim() == "<!-- consolidated -->")
+            .unwrap_or(false)
+        {
+            continue;
+        }
+        lines.insert(insert_at, "<!-- consolidated -->".to_string());
+    }
+
+    fs::write(path, lines.join("\n") + "\n")
+}
+
+/// Hex-encoded SHA256 of `text`, used to detect unchanged file content.
+fn sha256_hex(text: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(text.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Source file's last-modified time as a Unix timestamp (0 if unavailable).
+fn file_mtime(path: &Path) -> i64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Deterministic chunk ID derived from its source path and line range, so
+/// a chunk that doesn't move keeps the same ID across rebuilds instead of
+/// getting a fresh random one every time.
+fn deterministic_chunk_id(path: &str, start_line: usize, end_line: usize) -> String {
+    sha256_hex(&format!("{}:{}-{}", path, start_line, end_line))
+}
+
+/// Find the last byte index `<= target` that lies on a UTF-8 char boundary,
+/// using the precomputed `char_indices` of `text`.
+fn char_boundary_at_or_before(text: &str, char_indices: &[usize], target: usize) -> usize {
+    if target >= text.len() {
+        return text.len();
+    }
+    match char_indices.binary_search(&target) {
+        Ok(i) => char_indices[i],
+        Err(0) => 0,
+        Err(i) => char_indices[i - 1],
+    }
+}
+
+/// One structural element of a markdown file (a heading line, a fenced
+/// code block kept intact, or a blank-line-separated paragraph), tagged
+/// with its 1-based source line range.
+struct Unit {
+    start_line: usize,
+    end_line: usize,
+    text: String,
+}
+
+/// Split markdown text into [`Unit`]s on structural boundaries: headings
+/// and fenced code blocks are their own unit, and runs of plain text
+/// between blank lines form paragraph units.
+fn split_into_units(text: &str) -> Vec<Unit> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut units = Vec::new();
+    let mut i = 0usize;
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        if line.trim().is_empty() {
+            i += 1;
+            continue;
+        }
+
+        if line.trim_start().starts_with("```") {
+            let start = i;
+            let mut end = i + 1;
+            while end < lines.len() && !lines[end].trim_start().starts_with("```") {
+                end += 1;
+            }
+            let end = end.min(lines.len() - 1);
+            units.push(Unit {
+                start_line: start + 1,
+                end_line: end + 1,
+                text: lines[start..=end].join("\n"),
+            });
+            i = end + 1;
+            continue;
+        }
+
+        if line.trim_start().starts_with('#') {
+            units.push(Unit {
+                start_line: i + 1,
+                end_line: i + 1,
+                text: line.to_string(),
+            });
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let mut end = i;
+        while end < lines.len() {
+            let l = lines[end];
+            if l.trim().is_empty() || l.trim_start().starts_with('#') || l.trim_start().starts_with("```")
+            {
+                break;
+            }
+            end += 1;
+        }
+        let end = end - 1;
+        units.push(Unit {
+            start_line: start + 1,
+            end_line: end + 1,
+            text: lines[start..=end].join("\n"),
+        });
+        i = end + 1;
+    }
+
+    units
+}
+
+/// Pack adjacent [`Unit`]s into chunks up to `budget` chars, overlapping
+/// consecutive chunks by up to `overlap` chars of trailing units so
+/// context isn't lost at a chunk boundary. A single unit larger than
+/// `budget` (e.g. a long code block) is split on its own, always on
+/// `char_indices` boundaries, and keeps its original line range.
+fn pack_units(units: &[Unit], budget: usize, overlap: usize) -> Vec<Unit> {
+    let mut normalized: Vec<Unit> = Vec::new();
+    for u in units {
+        if u.text.chars().count() <= budget {
+            normalized.push(Unit {
+                start_line: u.start_line,
+                end_line: u.end_line,
+                text: u.text.clone(),
+            });
+            continue;
+        }
+        let char_indices: Vec<usize> = u.text.char_indices().map(|(i, _)| i).collect();
+        let len = u.text.len();
+        let mut start = 0usize;
+        while start < len {
+            let end = char_boundary_at_or_before(&u.text, &char_indices, (start + budget).min(len));
+            normalized.push(Unit {
+                start_line: u.start_line,
+                end_line: u.end_line,
+                text: u.text[start..end].to_string(),
+            });
+            if end >= len {
+                break;
+            }
+            start = end;
+        }
+    }
+
+    let mut chunks = Vec::new();
+    let mut i = 0usize;
+    while i < normalized.len() {
+        let start_line = normalized[i].start_line;
+        let mut texts: Vec<&str> = Vec::new();
+        let mut cur_len = 0usize;
+        let mut j = i;
+        while j < normalized.len() {
+            let ulen = normalized[j].text.chars().count();
+            if !texts.is_empty() && cur_len + ulen > budget {
+                break;
+            }
+            texts.push(normalized[j].text.as_str());
+            cur_len += ulen;
+            j += 1;
+        }
+        let end_line = normalized[j - 1].end_line;
+        chunks.push(Unit {
+            start_line,
+            end_line,
+            text: texts.join("\n\n"),
+        });
+
+        if j >= normalized.len() {
+            break;
+        }
+
+        // Carry trailing units whose combined length is within `overlap`
+        // into the start of the next chunk.
+        let mut k = j;
+        let mut carried = 0usize;
+        while k > i {
+            let ulen = normalized[k - 1].text.chars().count();
+            if carried + ulen > overlap {
+                break;
+            }
+            carried += ulen;
+            k -= 1;
+        }
+        i = if k > i { k } else { j };
+    }
+
+    chunks
+}
+
+/// On-disk index format: the embedding provider + dimensionality it was
+/// built with, so `search` can detect a provider/model change and rebuild
+/// instead of comparing incompatible vectors, plus a per-file cache keyed
+/// by each source file's relative path so `build_index` can skip files
+/// whose content hasn't changed.
 #[derive(Serialize, Deserialize)]
+struct IndexFile {
+    provider: String,
+    dimensions: usize,
+    files: HashMap<String, FileIndex>,
+}
+
+/// Cached chunk entries for one source file, plus the content hash (and
+/// mtime, kept for diagnostics) they were derived from - `build_index`
+/// only re-chunks and re-embeds a file when its hash no longer matches.
+#[derive(Serialize, Deserialize, Clone)]
+struct FileIndex {
+    content_hash: String,
+    mtime: i64,
+    entries: Vec<IndexEntry>,
+}
+
+/// Flattened view of an [`IndexFile`] used for searching.
+struct SearchIndex {
+    provider: String,
+    dimensions: usize,
+    entries: Vec<IndexEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 struct IndexEntry {
     id: String,
     path: String,
@@ -363,8 +989,158 @@ struct IndexEntry {
     vector: Vec<f32>,
 }
 
-/// Create a deterministic local embedding for `text` using SHA256.
-/// This is a placeholder for a real embedding API and yields a fixed-length vector.
+/// A source of text embeddings for the memory index. Implementations are
+/// chosen once at [`MemoryStore`] construction (via [`select_provider`])
+/// and boxed so the store doesn't care whether vectors come from a remote
+/// API, a local Ollama model, or the deterministic hash fallback.
+trait EmbeddingProvider: Send + Sync {
+    /// Embed a batch of texts in as few round-trips as the provider allows.
+    /// Implementations should return vectors in the same order as `texts`.
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, String>;
+
+    /// Dimensionality of the vectors this provider produces.
+    fn dimensions(&self) -> usize;
+
+    /// Stable identifier persisted in `.index.json` to detect provider
+    /// changes between a stored index and the currently configured store.
+    fn name(&self) -> &str;
+}
+
+/// Embed `texts` in fixed-size batches rather than one HTTP call per chunk,
+/// normalizing every vector to unit length so `cosine_similarity` can use a
+/// plain dot product.
+fn embed_in_batches(
+    provider: &dyn EmbeddingProvider,
+    texts: &[&str],
+) -> Result<Vec<Vec<f32>>, String> {
+    const BATCH_SIZE: usize = 16;
+    let mut vectors = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(BATCH_SIZE) {
+        vectors.extend(provider.embed_batch(batch)?.into_iter().map(normalize));
+    }
+    Ok(vectors)
+}
+
+fn normalize(mut vec: Vec<f32>) -> Vec<f32> {
+    let norm: f32 = vec.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 1e-12 {
+        for v in &mut vec {
+            *v /= norm;
+        }
+    }
+    vec
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+    dot
+}
+
+/// Split on whitespace/punctuation and lowercase, for BM25 term matching.
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// BM25 score of `query_terms` against each entry's chunk text
+/// (`k1=1.2`, `b=0.75`), 0.0 for entries with no matching term.
+fn bm25_scores(entries: &[IndexEntry], query_terms: &[String]) -> Vec<f32> {
+    use std::collections::{HashMap, HashSet};
+
+    const K1: f32 = 1.2;
+    const B: f32 = 0.75;
+
+    let n = entries.len() as f32;
+    if n == 0.0 {
+        return Vec::new();
+    }
+
+    let doc_tokens: Vec<Vec<String>> = entries.iter().map(|e| tokenize(&e.text)).collect();
+    let doc_lens: Vec<f32> = doc_tokens.iter().map(|t| t.len() as f32).collect();
+    let avgdl = (doc_lens.iter().sum::<f32>() / n).max(1.0);
+
+    let mut doc_freq: HashMap<&str, usize> = HashMap::new();
+    for toks in &doc_tokens {
+        let unique: HashSet<&str> = toks.iter().map(|t| t.as_str()).collect();
+        for term in unique {
+            *doc_freq.entry(term).or_insert(0) += 1;
+        }
+    }
+
+    doc_tokens
+        .iter()
+        .zip(doc_lens.iter())
+        .map(|(toks, &dl)| {
+            let mut term_freq: HashMap<&str, usize> = HashMap::new();
+            for t in toks {
+                *term_freq.entry(t.as_str()).or_insert(0) += 1;
+            }
+
+            query_terms
+                .iter()
+                .map(|term| {
+                    let tf = *term_freq.get(term.as_str()).unwrap_or(&0) as f32;
+                    if tf == 0.0 {
+                        return 0.0;
+                    }
+                    let df = *doc_freq.get(term.as_str()).unwrap_or(&0) as f32;
+                    let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
+                    idf * (tf * (K1 + 1.0)) / (tf + K1 * (1.0 - B + B * dl / avgdl))
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Fuse two per-entry score lists with Reciprocal Rank Fusion: each list is
+/// ranked independently (1-based, entries with a score of zero or below
+/// excluded so they don't contribute), and an entry's fused score is the sum of
+/// `1/(60 + rank)` over the lists it appears in.
+fn reciprocal_rank_fusion(a: &[f32], b: &[f32]) -> Vec<f32> {
+    const RRF_K: f32 = 60.0;
+    let n = a.len().max(b.len());
+
+    let mut fused = vec![0.0f32; n];
+    for scores in [a, b] {
+        let mut ranked: Vec<usize> = (0..scores.len()).filter(|&i| scores[i] > 0.0).collect();
+        ranked.sort_by(|&i, &j| {
+            scores[j]
+                .partial_cmp(&scores[i])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        for (rank, idx) in ranked.into_iter().enumerate() {
+            fused[idx] += 1.0 / (RRF_K + (rank + 1) as f32);
+        }
+    }
+    fused
+}
+
+/// Deterministic, offline embedding used when no remote provider is
+/// configured. Not semantically meaningful - just a fixed-length vector
+/// derived from the text's SHA256 digest, so indexing/search always work
+/// without an external API.
+struct HashEmbeddingProvider;
+
+impl EmbeddingProvider for HashEmbeddingProvider {
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, String> {
+        Ok(texts.iter().map(|t| embed_local(t)).collect())
+    }
+
+    fn dimensions(&self) -> usize {
+        64
+    }
+
+    fn name(&self) -> &str {
+        "hash"
+    }
+}
+
 fn embed_local(text: &str) -> Vec<f32> {
     let mut hasher = Sha256::new();
     hasher.update(text.as_bytes());
@@ -386,55 +1162,149 @@ fn embed_local(text: &str) -> Vec<f32> {
     vec
 }
 
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    if a.len() != b.len() || a.is_empty() {
-        return 0.0;
+/// Embedding provider backed by an OpenAI-compatible `/v1/embeddings`
+/// endpoint (OpenAI itself, OpenRouter, or any compatible gateway).
+struct OpenAiEmbeddingProvider {
+    api_base: String,
+    api_key: String,
+    model: String,
+}
+
+impl EmbeddingProvider for OpenAiEmbeddingProvider {
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, String> {
+        let url = format!("{}/v1/embeddings", self.api_base.trim_end_matches('/'));
+        let client = Client::new();
+        let body = serde_json::json!({"model": self.model, "input": texts});
+
+        let resp = client
+            .post(&url)
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .send()
+            .map_err(|e| e.to_string())?;
+        if !resp.status().is_success() {
+            return Err(format!("embeddings request failed: {}", resp.status()));
+        }
+        let v: Value = resp.json().map_err(|e| e.to_string())?;
+        let data = v
+            .get("data")
+            .and_then(|d| d.as_array())
+            .ok_or("missing 'data' in embeddings response")?;
+
+        data.iter()
+            .map(|item| {
+                let arr = item
+                    .get("embedding")
+                    .and_then(|e| e.as_array())
+                    .ok_or("missing 'embedding' in response item")?;
+                Ok(arr
+                    .iter()
+                    .filter_map(|n| n.as_f64().map(|f| f as f32))
+                    .collect())
+            })
+            .collect()
     }
-    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-    dot
+
+    fn dimensions(&self) -> usize {
+        match self.model.as_str() {
+            "text-embedding-3-large" => 3072,
+            _ => 1536,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "openai"
+    }
+}
+
+/// Embedding provider backed by a local Ollama server's `/api/embeddings`
+/// endpoint. That endpoint takes a single prompt per call, so batching
+/// here just means one HTTP round-trip per text rather than true batching.
+struct OllamaEmbeddingProvider {
+    base_url: String,
+    model: String,
 }
 
-/// Try remote embedding via OpenAI-compatible endpoint (OPENAI_API_BASE/OPENAI_API_KEY or OPENROUTER_API_KEY).
-/// Returns None on any failure; caller should fall back to local embedding.
-fn embed_remote(text: &str) -> Option<Vec<f32>> {
+impl EmbeddingProvider for OllamaEmbeddingProvider {
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, String> {
+        let url = format!("{}/api/embeddings", self.base_url.trim_end_matches('/'));
+        let client = Client::new();
+
+        texts
+            .iter()
+            .map(|text| {
+                let body = serde_json::json!({"model": self.model, "prompt": text});
+                let resp = client.post(&url).json(&body).send().map_err(|e| e.to_string())?;
+                if !resp.status().is_success() {
+                    return Err(format!("ollama embeddings request failed: {}", resp.status()));
+                }
+                let v: Value = resp.json().map_err(|e| e.to_string())?;
+                let arr = v
+                    .get("embedding")
+                    .and_then(|e| e.as_array())
+                    .ok_or("missing 'embedding' in ollama response")?;
+                Ok(arr
+                    .iter()
+                    .filter_map(|n| n.as_f64().map(|f| f as f32))
+                    .collect())
+            })
+            .collect()
+    }
+
+    fn dimensions(&self) -> usize {
+        768
+    }
+
+    fn name(&self) -> &str {
+        "ollama"
+    }
+}
+
+/// Pick an [`EmbeddingProvider`] from the environment: an explicit
+/// `MEMORY_EMBEDDING_PROVIDER` (`"openai"`, `"ollama"`, or `"hash"`) wins,
+/// otherwise prefer OpenAI-compatible credentials, then a configured
+/// Ollama host, and fall back to the offline hash embedding.
+fn select_provider() -> Box<dyn EmbeddingProvider> {
+    let explicit = env::var("MEMORY_EMBEDDING_PROVIDER").ok();
+
+    match explicit.as_deref() {
+        Some("openai") => {
+            if let Some(p) = openai_provider_from_env() {
+                return Box::new(p);
+            }
+        }
+        Some("ollama") => return Box::new(ollama_provider_from_env()),
+        Some("hash") => return Box::new(HashEmbeddingProvider),
+        _ => {}
+    }
+
+    if let Some(p) = openai_provider_from_env() {
+        return Box::new(p);
+    }
+    if env::var("OLLAMA_BASE_URL").is_ok() || env::var("OLLAMA_HOST").is_ok() {
+        return Box::new(ollama_provider_from_env());
+    }
+    Box::new(HashEmbeddingProvider)
+}
+
+fn openai_provider_from_env() -> Option<OpenAiEmbeddingProvider> {
     let api_key = env::var("OPENAI_API_KEY")
         .ok()
         .or_else(|| env::var("OPENROUTER_API_KEY").ok())?;
-
     let api_base =
         env::var("OPENAI_API_BASE").unwrap_or_else(|_| "https://api.openai.com".to_string());
-    let url = format!("{}/v1/embeddings", api_base.trim_end_matches('/'));
-
-    let client = Client::new();
-    let body = serde_json::json!({"model": "text-embedding-3-small", "input": text});
-
-    let resp = client
-        .post(&url)
-        .bearer_auth(api_key)
-        .json(&body)
-        .send()
-        .ok()?;
-    if !resp.status().is_success() {
-        return None;
-    }
-    let v: Value = resp.json().ok()?;
-    let arr = v.get("data")?.get(0)?.get("embedding")?.as_array()?;
-    let vec: Vec<f32> = arr
-        .iter()
-        .filter_map(|n| n.as_f64().map(|f| f as f32))
-        .collect();
-    if vec.is_empty() {
-        None
-    } else {
-        Some(vec)
-    }
+    let model = env::var("MEMORY_EMBEDDING_MODEL").unwrap_or_else(|_| "text-embedding-3-small".to_string());
+    Some(OpenAiEmbeddingProvider {
+        api_base,
+        api_key,
+        model,
+    })
 }
 
-/// Embed text using remote provider when available, otherwise fall back to deterministic local embedding.
-fn embed_text(text: &str) -> Vec<f32> {
-    if let Some(v) = embed_remote(text) {
-        v
-    } else {
-        embed_local(text)
-    }
+fn ollama_provider_from_env() -> OllamaEmbeddingProvider {
+    let base_url = env::var("OLLAMA_BASE_URL")
+        .or_else(|_| env::var("OLLAMA_HOST"))
+        .unwrap_or_else(|_| "http://localhost:11434".to_string());
+    let model = env::var("MEMORY_EMBEDDING_MODEL").unwrap_or_else(|_| "nomic-embed-text".to_string());
+    OllamaEmbeddingProvider { base_url, model }
 }
@@ -115,6 +115,30 @@ impl InboundMessage {
     }
 }
 
+impl InboundMessage {
+    /// Build from plain fields with no metadata - used to reconstruct a
+    /// message decoded off an external byte stream (see `bus::InboundCodec`),
+    /// which has no way to carry arbitrary Python metadata objects.
+    pub(crate) fn from_wire(
+        channel: String,
+        sender_id: String,
+        chat_id: String,
+        content: String,
+        timestamp: f64,
+        media: Vec<String>,
+    ) -> Self {
+        Self {
+            channel,
+            sender_id,
+            chat_id,
+            content,
+            timestamp,
+            media,
+            metadata: HashMap::new(),
+        }
+    }
+}
+
 /// Message to send to a chat channel.
 #[pyclass]
 #[derive(Clone)]
@@ -193,3 +217,25 @@ impl OutboundMessage {
         )
     }
 }
+
+impl OutboundMessage {
+    /// Build from plain fields with no metadata - the write-side
+    /// counterpart of [`InboundMessage::from_wire`], used when decoding an
+    /// outbound message off an external byte stream.
+    pub(crate) fn from_wire(
+        channel: String,
+        chat_id: String,
+        content: String,
+        reply_to: Option<String>,
+        media: Vec<String>,
+    ) -> Self {
+        Self {
+            channel,
+            chat_id,
+            content,
+            reply_to,
+            media,
+            metadata: HashMap::new(),
+        }
+    }
+}
@@ -1,14 +1,40 @@
 //! Session management for conversation history.
 
+use fs2::FileExt;
+use lru::LruCache;
 use parking_lot::Mutex;
 use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyList, PyString};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Number of rotated `.bak` generations kept per session file.
+const MAX_SESSION_BACKUPS: usize = 3;
+
+/// Default `SessionManager.cache_size` when the caller doesn't pick one -
+/// enough to keep a long-running agent's recent sessions warm without
+/// holding every conversation it has ever touched in memory.
+const DEFAULT_CACHE_SIZE: usize = 1000;
+
+/// Reserved session key for the scratch session `SessionManager::get_temp`
+/// hands out - a session under this key is always ephemeral, even if it
+/// reaches `save`/`append_message` through the ordinary key-based API.
+const TEMP_SESSION_KEY: &str = "__temp__";
+
+/// How many times to retry acquiring an advisory lock on a session file
+/// before giving up and surfacing `PyBlockingIOError` to Python.
+const LOCK_RETRY_ATTEMPTS: u32 = 10;
+
+/// Base backoff between lock retries; attempt `n` waits `n * this`.
+const LOCK_RETRY_BACKOFF: Duration = Duration::from_millis(25);
 
 /// A conversation message.
 #[derive(Clone, Serialize, Deserialize)]
@@ -28,6 +54,10 @@ struct SessionMetadata {
     created_at: String,
     updated_at: String,
     metadata: HashMap<String, serde_json::Value>,
+    /// Tag of the `SessionManager` schema config active when this file was
+    /// written (see `SessionManager::set_schema`); `None` if none was set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema_version: Option<String>,
 }
 
 /// A conversation session.
@@ -39,6 +69,13 @@ pub struct Session {
     created_at: String,
     updated_at: String,
     metadata: HashMap<String, serde_json::Value>,
+    /// Index of the first message not yet captured by a full `save()`
+    /// digest checkpoint, or `None` if every message is covered. Set by
+    /// `add_message`/`SessionManager::append_message`, cleared by `save`.
+    dirty_since: Option<usize>,
+    /// A throwaway session (see `SessionManager::get_temp`) that behaves
+    /// like any other `Session` in memory but is never written to disk.
+    ephemeral: bool,
 }
 
 #[pymethods]
@@ -104,16 +141,22 @@ impl Session {
             HashMap::new()
         };
 
+        let ephemeral = key == TEMP_SESSION_KEY;
         Ok(Session {
             key,
             messages: msgs,
             created_at: created_at.unwrap_or_else(|| now.clone()),
             updated_at: updated_at.unwrap_or(now),
             metadata: meta,
+            dirty_since: None,
+            ephemeral,
         })
     }
 
-    /// Add a message to the session.
+    /// Add a message to the session. Like `set_metadata`, this is an
+    /// in-memory-only mutation with no manager reference, so a configured
+    /// message schema (see `SessionManager::set_schema`) is enforced at
+    /// `SessionManager.save`/`append_message` time rather than here.
     #[pyo3(signature = (role, content, **kwargs))]
     fn add_message(
         &mut self,
@@ -134,6 +177,9 @@ impl Session {
             }
         }
 
+        if self.dirty_since.is_none() {
+            self.dirty_since = Some(self.messages.len());
+        }
         self.messages.push(Message {
             role,
             content,
@@ -145,16 +191,66 @@ impl Session {
     }
 
     /// Get message history for LLM context.
-    #[pyo3(signature = (max_messages=50))]
-    fn get_history(&self, py: Python<'_>, max_messages: usize) -> PyResult<Py<PyList>> {
-        let start = if self.messages.len() > max_messages {
-            self.messages.len() - max_messages
-        } else {
-            0
+    ///
+    /// With only `max_messages` (the default), returns the last
+    /// `max_messages` raw messages, as before. When `max_tokens` is given,
+    /// that's ignored in favor of a token-budget walk: messages are
+    /// scanned newest-to-oldest accumulating an approximate token count
+    /// (`extra["_tokens"]` when known, else `content.len()/4`) until the
+    /// budget is spent; everything older is folded into one synthetic
+    /// `{"role": "system", "content": <summary>}` message placed at the
+    /// front. `<summary>` comes from `summarizer(evicted_messages)` if
+    /// given, else a deterministic truncated concatenation, and is cached
+    /// in session metadata keyed by the exact span it covers so the same
+    /// span isn't re-summarized on a later call.
+    #[pyo3(signature = (max_messages=50, max_tokens=None, summarizer=None))]
+    fn get_history(
+        &mut self,
+        py: Python<'_>,
+        max_messages: usize,
+        max_tokens: Option<usize>,
+        summarizer: Option<PyObject>,
+    ) -> PyResult<Py<PyList>> {
+        let Some(budget) = max_tokens else {
+            let start = if self.messages.len() > max_messages {
+                self.messages.len() - max_messages
+            } else {
+                0
+            };
+
+            let result = PyList::empty(py);
+            for msg in &self.messages[start..] {
+                let dict = PyDict::new(py);
+                dict.set_item("role", &msg.role)?;
+                dict.set_item("content", &msg.content)?;
+                result.append(dict)?;
+            }
+            return Ok(result.into());
         };
 
+        // Walk newest-to-oldest accumulating tokens until the budget is
+        // spent; `cut` is the index of the oldest message still kept.
+        let mut cut = self.messages.len();
+        let mut spent = 0usize;
+        for (i, msg) in self.messages.iter().enumerate().rev() {
+            let tokens = estimate_tokens(msg);
+            if spent > 0 && spent + tokens > budget {
+                cut = i + 1;
+                break;
+            }
+            spent += tokens;
+            cut = i;
+        }
+
         let result = PyList::empty(py);
-        for msg in &self.messages[start..] {
+        if cut > 0 {
+            let summary = self.summary_for_span(py, 0, cut, summarizer)?;
+            let summary_dict = PyDict::new(py);
+            summary_dict.set_item("role", "system")?;
+            summary_dict.set_item("content", summary)?;
+            result.append(summary_dict)?;
+        }
+        for msg in &self.messages[cut..] {
             let dict = PyDict::new(py);
             dict.set_item("role", &msg.role)?;
             dict.set_item("content", &msg.content)?;
@@ -184,6 +280,19 @@ impl Session {
         &self.updated_at
     }
 
+    /// Index of the first message not yet covered by a full `save()`
+    /// digest checkpoint, or `None` if the session has nothing unsaved.
+    #[getter]
+    fn dirty_since(&self) -> Option<usize> {
+        self.dirty_since
+    }
+
+    /// Whether this is a throwaway session that never touches disk.
+    #[getter]
+    fn ephemeral(&self) -> bool {
+        self.ephemeral
+    }
+
     /// Get messages as Python list.
     #[getter]
     fn messages(&self, py: Python<'_>) -> PyResult<Py<PyList>> {
@@ -211,7 +320,11 @@ impl Session {
         Ok(dict.into())
     }
 
-    /// Set metadata from Python dict.
+    /// Set metadata from Python dict. A `Session` on its own has no
+    /// reference back to the `SessionManager` that may have a metadata
+    /// schema configured (see `SessionManager::set_schema`), so malformed
+    /// metadata set here is only rejected once the session is persisted
+    /// via `SessionManager.save`.
     #[setter]
     fn set_metadata(&mut self, value: &Bound<'_, PyDict>) -> PyResult<()> {
         self.metadata = python_dict_to_json_map(value)?;
@@ -219,13 +332,110 @@ impl Session {
     }
 }
 
+impl Session {
+    /// Summary text for the message span `[start, end)`, computed once
+    /// and cached in `self.metadata` under a key naming the exact span so
+    /// a later `get_history` call over the same (unchanged) span skips
+    /// `summarizer` entirely.
+    fn summary_for_span(
+        &mut self,
+        py: Python<'_>,
+        start: usize,
+        end: usize,
+        summarizer: Option<PyObject>,
+    ) -> PyResult<String> {
+        let cache_key = format!("_summary:{}:{}", start, end);
+        if let Some(cached) = self.metadata.get(&cache_key) {
+            if let Some(summary) = cached.get("summary").and_then(|v| v.as_str()) {
+                return Ok(summary.to_string());
+            }
+        }
+
+        let evicted = &self.messages[start..end];
+        let summary = match summarizer {
+            Some(summarizer) => {
+                let dicts = PyList::empty(py);
+                for msg in evicted {
+                    let dict = PyDict::new(py);
+                    dict.set_item("role", &msg.role)?;
+                    dict.set_item("content", &msg.content)?;
+                    dicts.append(dict)?;
+                }
+                summarizer.call1(py, (dicts,))?.extract::<String>(py)?
+            }
+            None => default_summary(evicted),
+        };
+
+        self.metadata.insert(
+            cache_key,
+            serde_json::json!({
+                "_type": "summary",
+                "start": start,
+                "end": end,
+                "summary": summary,
+            }),
+        );
+
+        Ok(summary)
+    }
+}
+
+/// Approximate token count for `msg`: an explicit `_tokens` extra field
+/// when the caller provided one (e.g. from a model's usage response),
+/// else the cheap `content.len() / 4` heuristic.
+fn estimate_tokens(msg: &Message) -> usize {
+    msg.extra
+        .get("_tokens")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize)
+        .unwrap_or_else(|| msg.content.len() / 4)
+}
+
+/// Deterministic fallback summary when no `summarizer` callback is given:
+/// a `role: content` concatenation of the evicted span, truncated.
+fn default_summary(evicted: &[Message]) -> String {
+    const MAX_SUMMARY_CHARS: usize = 500;
+
+    let mut combined = String::new();
+    for msg in evicted {
+        if !combined.is_empty() {
+            combined.push(' ');
+        }
+        combined.push_str(&format!("{}: {}", msg.role, msg.content));
+    }
+
+    if combined.chars().count() > MAX_SUMMARY_CHARS {
+        let truncated: String = combined.chars().take(MAX_SUMMARY_CHARS).collect();
+        format!("{}...", truncated)
+    } else {
+        combined
+    }
+}
+
 /// Manages conversation sessions.
 #[pyclass]
 #[allow(dead_code)]
 pub struct SessionManager {
     workspace: PathBuf,
     sessions_dir: PathBuf,
-    cache: Arc<Mutex<HashMap<String, SessionData>>>,
+    /// Maximum number of sessions kept warm in `cache` at once.
+    #[pyo3(get)]
+    cache_size: usize,
+    cache: Arc<Mutex<LruCache<String, SessionData>>>,
+    /// Optional JSON Schema validation for messages/metadata, set via
+    /// `set_schema`. `None` fields mean "no schema configured" - validation
+    /// is skipped entirely until a schema is set.
+    schema: Mutex<SchemaConfig>,
+}
+
+/// Compiled JSON Schema validators plus the version tag persisted into a
+/// session file's metadata line, so `load` can warn when a file was
+/// written under a schema the current process isn't configured with.
+#[derive(Default)]
+struct SchemaConfig {
+    message: Option<Arc<jsonschema::Validator>>,
+    metadata: Option<Arc<jsonschema::Validator>>,
+    version: Option<String>,
 }
 
 /// Internal session data for caching.
@@ -235,6 +445,8 @@ struct SessionData {
     created_at: String,
     updated_at: String,
     metadata: HashMap<String, serde_json::Value>,
+    dirty_since: Option<usize>,
+    ephemeral: bool,
 }
 
 impl SessionData {
@@ -245,6 +457,18 @@ impl SessionData {
             created_at: self.created_at.clone(),
             updated_at: self.updated_at.clone(),
             metadata: self.metadata.clone(),
+            dirty_since: self.dirty_since,
+            ephemeral: self.ephemeral,
+        }
+    }
+
+    /// Cache a session after a full `save()` - the fresh digest checkpoint
+    /// covers every message, so the cached copy starts out clean even if
+    /// the `Session` passed in still carried a stale `dirty_since`.
+    fn from_saved_session(session: &Session) -> Self {
+        SessionData {
+            dirty_since: None,
+            ..Self::from_session(session)
         }
     }
 
@@ -255,6 +479,8 @@ impl SessionData {
             created_at: session.created_at.clone(),
             updated_at: session.updated_at.clone(),
             metadata: session.metadata.clone(),
+            dirty_since: session.dirty_since,
+            ephemeral: session.ephemeral,
         }
     }
 }
@@ -262,7 +488,8 @@ impl SessionData {
 #[pymethods]
 impl SessionManager {
     #[new]
-    fn new(workspace: PathBuf) -> PyResult<Self> {
+    #[pyo3(signature = (workspace, cache_size=None))]
+    fn new(workspace: PathBuf, cache_size: Option<usize>) -> PyResult<Self> {
         let sessions_dir = dirs::home_dir()
             .ok_or_else(|| {
                 PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Cannot find home directory")
@@ -278,18 +505,95 @@ impl SessionManager {
             ))
         })?;
 
+        let cache_size = cache_size.unwrap_or(DEFAULT_CACHE_SIZE).max(1);
+
         Ok(SessionManager {
             workspace,
             sessions_dir,
-            cache: Arc::new(Mutex::new(HashMap::new())),
+            cache_size,
+            cache: Arc::new(Mutex::new(LruCache::new(
+                NonZeroUsize::new(cache_size).expect("cache_size is clamped to at least 1"),
+            ))),
+            schema: Mutex::new(SchemaConfig::default()),
         })
     }
 
+    /// Set or clear opt-in JSON Schema (Draft 2020-12) validation for
+    /// messages and/or session metadata. Each argument is a schema given as
+    /// a JSON string; passing `None` leaves that object type unvalidated.
+    /// Once set, `append_message` and `save` reject anything that fails
+    /// validation with a `PyValueError` naming the failing JSON pointer.
+    /// `Session.add_message` and the `Session.metadata` setter do not
+    /// validate on their own; what they store is checked when the session
+    /// goes through `save`. The schema's content is tagged with a
+    /// SHA-256 version string that gets written into every session file
+    /// saved afterwards, so `load` can warn if a file was written under a
+    /// different schema than the one currently configured.
+    #[pyo3(signature = (message_schema=None, metadata_schema=None))]
+    fn set_schema(
+        &self,
+        message_schema: Option<String>,
+        metadata_schema: Option<String>,
+    ) -> PyResult<()> {
+        let compile = |raw: &str| -> PyResult<Arc<jsonschema::Validator>> {
+            let value: serde_json::Value = serde_json::from_str(raw).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "invalid schema JSON: {}",
+                    e
+                ))
+            })?;
+            let validator = jsonschema::validator_for(&value).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "invalid JSON Schema: {}",
+                    e
+                ))
+            })?;
+            Ok(Arc::new(validator))
+        };
+
+        let message = message_schema.as_deref().map(compile).transpose()?;
+        let metadata = metadata_schema.as_deref().map(compile).transpose()?;
+
+        let mut tagged = String::new();
+        if let Some(raw) = &message_schema {
+            tagged.push_str(raw);
+        }
+        if let Some(raw) = &metadata_schema {
+            tagged.push_str(raw);
+        }
+        let version = if tagged.is_empty() {
+            None
+        } else {
+            Some(sha256_hex(&tagged))
+        };
+
+        let mut schema = self.schema.lock();
+        schema.message = message;
+        schema.metadata = metadata;
+        schema.version = version;
+        Ok(())
+    }
+
+    /// Drop every cached session, flushing any not yet durably saved first.
+    fn cache_clear(&self) {
+        let mut cache = self.cache.lock();
+        let dirty: Vec<Session> = cache
+            .iter()
+            .filter(|(_, data)| !data.ephemeral && data.dirty_since.is_some())
+            .map(|(_, data)| data.to_session())
+            .collect();
+        cache.clear();
+        drop(cache);
+        for session in dirty {
+            let _ = self.persist_to_disk(&session);
+        }
+    }
+
     /// Get an existing session or create a new one.
-    fn get_or_create(&self, key: String) -> PyResult<Session> {
+    fn get_or_create(&self, py: Python<'_>, key: String) -> PyResult<Session> {
         // Check cache first
         {
-            let cache = self.cache.lock();
+            let mut cache = self.cache.lock();
             if let Some(data) = cache.get(&key) {
                 return Ok(data.to_session());
             }
@@ -308,8 +612,15 @@ impl SessionManager {
                     created_at: now.clone(),
                     updated_at: now,
                     metadata: HashMap::new(),
+                    dirty_since: None,
+                    ephemeral: key == TEMP_SESSION_KEY,
                 }
             }
+            // A contended lock is an actionable condition, not a corrupt
+            // session - surface it instead of silently discarding history.
+            Err(e) if e.is_instance_of::<pyo3::exceptions::PyBlockingIOError>(py) => {
+                return Err(e)
+            }
             Err(_e) => {
                 // Log warning and create new session
                 let now = chrono::Utc::now()
@@ -321,6 +632,8 @@ impl SessionManager {
                     created_at: now.clone(),
                     updated_at: now,
                     metadata: HashMap::new(),
+                    dirty_since: None,
+                    ephemeral: key == TEMP_SESSION_KEY,
                 }
             }
         };
@@ -328,60 +641,260 @@ impl SessionManager {
         // Cache it
         {
             let mut cache = self.cache.lock();
-            cache.insert(key, SessionData::from_session(&session));
+            self.cache_put(&mut cache, key, SessionData::from_session(&session));
         }
 
         Ok(session)
     }
 
-    /// Save a session to disk.
-    fn save(&self, session: &Session) -> PyResult<()> {
-        let path = self.get_session_path(&session.key);
+    /// Get the shared scratch session under `TEMP_SESSION_KEY` - a session
+    /// that behaves normally in memory but is never written to disk (see
+    /// `Session.ephemeral`). Returns the cached copy if one already exists.
+    fn get_temp(&self) -> Session {
+        {
+            let mut cache = self.cache.lock();
+            if let Some(data) = cache.get(TEMP_SESSION_KEY) {
+                return data.to_session();
+            }
+        }
 
-        let mut file = File::create(&path).map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                "Failed to create session file: {}",
-                e
-            ))
-        })?;
+        let now = chrono::Utc::now()
+            .format("%Y-%m-%dT%H:%M:%S%.6f")
+            .to_string();
+        let session = Session {
+            key: TEMP_SESSION_KEY.to_string(),
+            messages: Vec::new(),
+            created_at: now.clone(),
+            updated_at: now,
+            metadata: HashMap::new(),
+            dirty_since: None,
+            ephemeral: true,
+        };
 
-        // Write metadata first
-        let metadata = SessionMetadata {
-            type_marker: "metadata".to_string(),
+        {
+            let mut cache = self.cache.lock();
+            self.cache_put(&mut cache, TEMP_SESSION_KEY.to_string(), SessionData::from_session(&session));
+        }
+
+        session
+    }
+
+    /// Promote an ephemeral session to a named, persisted one under
+    /// `new_key`, saving it to disk immediately. If `session` was the
+    /// shared temp session, its entry is dropped from the cache so a
+    /// subsequent `get_temp` starts a fresh scratch session.
+    fn persist(&self, session: &Session, new_key: String) -> PyResult<Session> {
+        let promoted = Session {
+            key: new_key.clone(),
+            messages: session.messages.clone(),
             created_at: session.created_at.clone(),
             updated_at: session.updated_at.clone(),
             metadata: session.metadata.clone(),
+            dirty_since: session.dirty_since,
+            ephemeral: false,
         };
-        let meta_json = serde_json::to_string(&metadata).map_err(|e| {
+
+        self.save(&promoted)?;
+
+        if session.key == TEMP_SESSION_KEY {
+            let mut cache = self.cache.lock();
+            cache.pop(TEMP_SESSION_KEY);
+        }
+
+        Ok(promoted)
+    }
+
+    /// Save a session to disk.
+    ///
+    /// Crash-safe: the full JSONL body is written to a sibling
+    /// `.jsonl.tmp` file and `fsync`'d, the previous file (if any) is
+    /// rotated into the `.bak` chain, and only then is the temp file
+    /// renamed over the real path - a crash or power loss can never leave
+    /// a half-written session file on disk. The file's trailing line
+    /// records a SHA-256 digest of the message body so `load` can detect
+    /// a truncated/corrupt file and fall back to the newest valid backup.
+    fn save(&self, session: &Session) -> PyResult<()> {
+        self.validate_metadata(&session.metadata)?;
+        for msg in &session.messages {
+            self.validate_message(msg)?;
+        }
+
+        // Ephemeral sessions (see `get_temp`/`TEMP_SESSION_KEY`) never touch
+        // disk - only the in-memory cache is kept up to date.
+        if session.ephemeral || session.key == TEMP_SESSION_KEY {
+            let mut cache = self.cache.lock();
+            self.cache_put(&mut cache, session.key.clone(), SessionData::from_saved_session(session));
+            return Ok(());
+        }
+
+        self.persist_to_disk(session)?;
+
+        // Update cache - the digest just written covers every message, so
+        // the cached copy is clean regardless of the passed-in session's
+        // own `dirty_since`.
+        {
+            let mut cache = self.cache.lock();
+            self.cache_put(&mut cache, session.key.clone(), SessionData::from_saved_session(session));
+        }
+
+        Ok(())
+    }
+
+    /// Append a single message to a session's file in O(1), instead of
+    /// `save`'s O(n) full rewrite - the hot path for a long-running
+    /// conversation. Writes the message line plus a trailing
+    /// `metadata_update` record (so `updated_at` advances without
+    /// rewriting the file's first line) directly via `OpenOptions::append`,
+    /// then updates the in-memory cache in place. `save` remains the way
+    /// to compact a session and refresh its digest/backup checkpoint.
+    #[pyo3(signature = (key, role, content, **kwargs))]
+    fn append_message(
+        &self,
+        key: String,
+        role: String,
+        content: String,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<()> {
+        let mut extra = HashMap::new();
+        if let Some(kw) = kwargs {
+            for (k, v) in kw.iter() {
+                let k: String = k.extract()?;
+                extra.insert(k, python_to_json(v)?);
+            }
+        }
+
+        let now = chrono::Utc::now()
+            .format("%Y-%m-%dT%H:%M:%S%.6f")
+            .to_string();
+        let message = Message {
+            role,
+            content,
+            timestamp: now.clone(),
+            extra,
+        };
+        self.validate_message(&message)?;
+        let msg_json = serde_json::to_string(&message).map_err(|e| {
             PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
-                "Failed to serialize metadata: {}",
+                "Failed to serialize message: {}",
                 e
             ))
         })?;
-        writeln!(file, "{}", meta_json).map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to write metadata: {}", e))
-        })?;
+        let metadata_update_json = serde_json::to_string(&serde_json::json!({
+            "_type": "metadata_update",
+            "updated_at": now,
+        }))
+        .expect("metadata_update record always serializes");
 
-        // Write messages
-        for msg in &session.messages {
-            let msg_json = serde_json::to_string(msg).map_err(|e| {
+        // The reserved temp key never touches disk - update the in-memory
+        // cache below and return.
+        if key == TEMP_SESSION_KEY {
+            let mut cache = self.cache.lock();
+            if cache.get(&key).is_none() {
+                self.cache_put(
+                    &mut cache,
+                    key.clone(),
+                    SessionData {
+                        key: key.clone(),
+                        messages: Vec::new(),
+                        created_at: now.clone(),
+                        updated_at: now.clone(),
+                        metadata: HashMap::new(),
+                        dirty_since: None,
+                        ephemeral: true,
+                    },
+                );
+            }
+            let data = cache.get_mut(&key).expect("just inserted above");
+            if data.dirty_since.is_none() {
+                data.dirty_since = Some(data.messages.len());
+            }
+            data.messages.push(message);
+            data.updated_at = now;
+            return Ok(());
+        }
+
+        // Make sure the cache entry reflects the session's full history
+        // before mutating it, the same way `get_or_create` does - otherwise
+        // a cache miss here would fabricate an empty session, and chunk3-6's
+        // LRU eviction would later rewrite the on-disk file with only this
+        // one message, destroying everything appended before it.
+        if self.cache.lock().get(&key).is_none() {
+            let seed = match self.load(&key)? {
+                Some(s) => SessionData::from_session(&s),
+                None => SessionData {
+                    key: key.clone(),
+                    messages: Vec::new(),
+                    created_at: now.clone(),
+                    updated_at: now.clone(),
+                    metadata: HashMap::new(),
+                    dirty_since: None,
+                    ephemeral: false,
+                },
+            };
+            let mut cache = self.cache.lock();
+            self.cache_put(&mut cache, key.clone(), seed);
+        }
+
+        let path = self.get_session_path(&key);
+        let _lock = acquire_lock(&path, true)?;
+
+        if !path.exists() {
+            let metadata = SessionMetadata {
+                type_marker: "metadata".to_string(),
+                created_at: now.clone(),
+                updated_at: now.clone(),
+                metadata: HashMap::new(),
+                schema_version: self.current_schema_version(),
+            };
+            let meta_json = serde_json::to_string(&metadata).map_err(|e| {
                 PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
-                    "Failed to serialize message: {}",
+                    "Failed to serialize metadata: {}",
+                    e
+                ))
+            })?;
+            fs::write(&path, format!("{}\n", meta_json)).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to create session file: {}",
+                    e
+                ))
+            })?;
+        }
+
+        {
+            let mut file = fs::OpenOptions::new().append(true).open(&path).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to open session file for append: {}",
                     e
                 ))
             })?;
             writeln!(file, "{}", msg_json).map_err(|e| {
                 PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
-                    "Failed to write message: {}",
+                    "Failed to append message: {}",
+                    e
+                ))
+            })?;
+            writeln!(file, "{}", metadata_update_json).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to append metadata update: {}",
+                    e
+                ))
+            })?;
+            file.sync_all().map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to fsync session file: {}",
                     e
                 ))
             })?;
         }
 
-        // Update cache
         {
             let mut cache = self.cache.lock();
-            cache.insert(session.key.clone(), SessionData::from_session(session));
+            let data = cache.get_mut(&key).expect("seeded above");
+            if data.dirty_since.is_none() {
+                data.dirty_since = Some(data.messages.len());
+            }
+            data.messages.push(message);
+            data.updated_at = now;
         }
 
         Ok(())
@@ -392,11 +905,12 @@ impl SessionManager {
         // Remove from cache
         {
             let mut cache = self.cache.lock();
-            cache.remove(&key);
+            cache.pop(&key);
         }
 
         // Remove file
         let path = self.get_session_path(&key);
+        let _lock = acquire_lock(&path, true)?;
         if path.exists() {
             fs::remove_file(&path).map_err(|e| {
                 PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
@@ -488,6 +1002,141 @@ impl SessionManager {
 
         Ok(result.into())
     }
+
+    /// Full-text search across every stored session's messages.
+    ///
+    /// Each file is streamed line-by-line rather than fully loaded, and is
+    /// skipped outright (via its metadata line) if `since` is given and
+    /// the file's `updated_at` is older. Matching is a case-insensitive
+    /// substring/term match against message `content`; hits are ranked by
+    /// term frequency (how many times `query` occurs in that message),
+    /// with ties broken by the owning session's `updated_at` (most recent
+    /// first). Returns up to `limit` hits as dicts of `{key,
+    /// message_index, role, timestamp, snippet}`.
+    #[pyo3(signature = (query, role=None, since=None, limit=50))]
+    fn search(
+        &self,
+        py: Python<'_>,
+        query: String,
+        role: Option<String>,
+        since: Option<String>,
+        limit: usize,
+    ) -> PyResult<Py<PyList>> {
+        let needle = query.to_lowercase();
+
+        let entries = fs::read_dir(&self.sessions_dir).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to read sessions directory: {}",
+                e
+            ))
+        })?;
+
+        // (term frequency, session updated_at, hit)
+        let mut hits: Vec<(usize, String, SearchHit)> = Vec::new();
+
+        for entry in entries {
+            let Ok(entry) = entry else { continue };
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+                continue;
+            }
+
+            let Ok(file) = File::open(&path) else { continue };
+            let mut lines = BufReader::new(file).lines();
+
+            let Some(Ok(first_line)) = lines.next() else {
+                continue;
+            };
+            let Ok(meta) = serde_json::from_str::<serde_json::Value>(&first_line) else {
+                continue;
+            };
+            if meta.get("_type").and_then(|v| v.as_str()) != Some("metadata") {
+                continue;
+            }
+            let updated_at = meta
+                .get("updated_at")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+            if let Some(since) = &since {
+                if &updated_at < since {
+                    continue;
+                }
+            }
+
+            let key = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(|s| s.replace("_", ":"))
+                .unwrap_or_default();
+
+            let mut message_index = 0usize;
+            for line in lines.map_while(Result::ok) {
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+                    continue;
+                };
+                if matches!(
+                    value.get("_type").and_then(|v| v.as_str()),
+                    Some("digest") | Some("metadata_update")
+                ) {
+                    continue;
+                }
+
+                let idx = message_index;
+                message_index += 1;
+
+                let msg_role = value.get("role").and_then(|v| v.as_str()).unwrap_or("");
+                if let Some(wanted) = &role {
+                    if msg_role != wanted {
+                        continue;
+                    }
+                }
+
+                let content = value.get("content").and_then(|v| v.as_str()).unwrap_or("");
+                let term_count = content.to_lowercase().matches(&needle).count();
+                if term_count == 0 {
+                    continue;
+                }
+
+                let timestamp = value
+                    .get("timestamp")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string();
+
+                hits.push((
+                    term_count,
+                    updated_at.clone(),
+                    SearchHit {
+                        key: key.clone(),
+                        message_index: idx,
+                        role: msg_role.to_string(),
+                        timestamp,
+                        snippet: search_snippet(content, &needle),
+                    },
+                ));
+            }
+        }
+
+        hits.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| b.1.cmp(&a.1)));
+
+        let result = PyList::empty(py);
+        for (_, _, hit) in hits.into_iter().take(limit) {
+            let dict = PyDict::new(py);
+            dict.set_item("key", hit.key)?;
+            dict.set_item("message_index", hit.message_index)?;
+            dict.set_item("role", hit.role)?;
+            dict.set_item("timestamp", hit.timestamp)?;
+            dict.set_item("snippet", hit.snippet)?;
+            result.append(dict)?;
+        }
+
+        Ok(result.into())
+    }
 }
 
 impl SessionManager {
@@ -496,62 +1145,460 @@ impl SessionManager {
         self.sessions_dir.join(format!("{}.jsonl", safe_key))
     }
 
-    fn load(&self, key: &str) -> Result<Option<Session>, String> {
+    /// Validate a message against the configured message schema, if any.
+    fn validate_message(&self, msg: &Message) -> PyResult<()> {
+        let schema = self.schema.lock();
+        let Some(validator) = schema.message.as_ref() else {
+            return Ok(());
+        };
+        let value = serde_json::to_value(msg).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "failed to serialize message for schema validation: {}",
+                e
+            ))
+        })?;
+        validator.validate(&value).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "message failed schema validation at {}: {}",
+                e.instance_path, e
+            ))
+        })
+    }
+
+    /// Validate session metadata against the configured metadata schema,
+    /// if any.
+    fn validate_metadata(&self, metadata: &HashMap<String, serde_json::Value>) -> PyResult<()> {
+        let schema = self.schema.lock();
+        let Some(validator) = schema.metadata.as_ref() else {
+            return Ok(());
+        };
+        let value = serde_json::to_value(metadata).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "failed to serialize metadata for schema validation: {}",
+                e
+            ))
+        })?;
+        validator.validate(&value).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "metadata failed schema validation at {}: {}",
+                e.instance_path, e
+            ))
+        })
+    }
+
+    /// The SHA-256 tag of the currently configured schema(s), if any -
+    /// written into every session file saved from this point on.
+    fn current_schema_version(&self) -> Option<String> {
+        self.schema.lock().version.clone()
+    }
+
+    /// Warn on stderr if a just-loaded file's recorded schema version
+    /// doesn't match what's currently configured - the data is still
+    /// loaded as-is, this is advisory only.
+    fn warn_on_schema_mismatch(&self, file_schema_version: &Option<String>) {
+        if let (Some(file_version), Some(current_version)) =
+            (file_schema_version, &self.current_schema_version())
+        {
+            if file_version != current_version {
+                eprintln!(
+                    "warning: session file was saved under schema version {} but the \
+                     current schema version is {} - validation may be inconsistent",
+                    file_version, current_version
+                );
+            }
+        }
+    }
+
+    /// Write a session's full JSONL body to disk, crash-safely (see
+    /// `save`'s doc comment), without touching the cache. Split out of
+    /// `save` so the LRU cache can flush an evicted-but-dirty entry to
+    /// disk without re-inserting it back into the cache it was just
+    /// evicted from.
+    fn persist_to_disk(&self, session: &Session) -> PyResult<()> {
+        let path = self.get_session_path(&session.key);
+        let tmp_path = path.with_extension("jsonl.tmp");
+        let _lock = acquire_lock(&path, true)?;
+
+        let metadata = SessionMetadata {
+            type_marker: "metadata".to_string(),
+            created_at: session.created_at.clone(),
+            updated_at: session.updated_at.clone(),
+            metadata: session.metadata.clone(),
+            schema_version: self.current_schema_version(),
+        };
+        let meta_json = serde_json::to_string(&metadata).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Failed to serialize metadata: {}",
+                e
+            ))
+        })?;
+
+        let mut message_lines = Vec::with_capacity(session.messages.len());
+        for msg in &session.messages {
+            let msg_json = serde_json::to_string(msg).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Failed to serialize message: {}",
+                    e
+                ))
+            })?;
+            message_lines.push(msg_json);
+        }
+
+        let body = message_lines.join("\n");
+        let digest_json = serde_json::to_string(&serde_json::json!({
+            "_type": "digest",
+            "sha256": sha256_hex(&body),
+        }))
+        .expect("digest record always serializes");
+
+        {
+            let mut tmp_file = File::create(&tmp_path).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to create temp session file: {}",
+                    e
+                ))
+            })?;
+            writeln!(tmp_file, "{}", meta_json).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to write metadata: {}",
+                    e
+                ))
+            })?;
+            if !body.is_empty() {
+                writeln!(tmp_file, "{}", body).map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                        "Failed to write messages: {}",
+                        e
+                    ))
+                })?;
+            }
+            writeln!(tmp_file, "{}", digest_json).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to write digest: {}",
+                    e
+                ))
+            })?;
+            tmp_file.sync_all().map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                    "Failed to fsync session file: {}",
+                    e
+                ))
+            })?;
+        }
+
+        rotate_backups(&path);
+
+        fs::rename(&tmp_path, &path).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to finalize session file: {}",
+                e
+            ))
+        })?;
+
+        Ok(())
+    }
+
+    /// Insert into the LRU cache, flushing any entry it evicts.
+    ///
+    /// Sessions are already durable on disk once saved, so evicting a
+    /// clean entry is free; an entry with messages appended since its
+    /// last `save()` (`dirty_since.is_some()`) is written out first so an
+    /// eviction never silently drops unsaved history. Ephemeral entries
+    /// are never flushed - they have nowhere to go.
+    fn cache_put(&self, cache: &mut LruCache<String, SessionData>, key: String, data: SessionData) {
+        let replaced_key = key.clone();
+        if let Some((evicted_key, evicted)) = cache.push(key, data) {
+            // `push` returns `Some` both when it evicts a *different* key
+            // at capacity and when `key` already had an entry that's just
+            // being replaced - the latter is not an eviction, and the
+            // "evicted" value is the entry's stale pre-replacement state
+            // (e.g. `save()` just persisted fresher data under the same
+            // key), so flushing it here would overwrite the fresh write
+            // with stale data.
+            if evicted_key != replaced_key && !evicted.ephemeral && evicted.dirty_since.is_some() {
+                // Best-effort: there's no Python caller left to report a
+                // write failure to at eviction time.
+                let _ = self.persist_to_disk(&evicted.to_session());
+            }
+        }
+    }
+
+    fn load(&self, key: &str) -> PyResult<Option<Session>> {
         let path = self.get_session_path(key);
 
         if !path.exists() {
             return Ok(None);
         }
 
-        let file = File::open(&path).map_err(|e| e.to_string())?;
-        let reader = BufReader::new(file);
-
-        let mut messages = Vec::new();
-        let mut metadata = HashMap::new();
-        let mut created_at = None;
+        {
+            let lock = acquire_lock(&path, false)?;
+            let loaded = load_session_file(&path, key);
+            drop(lock);
+            if let Some((session, schema_version)) = loaded {
+                self.warn_on_schema_mismatch(&schema_version);
+                return Ok(Some(session));
+            }
+        }
 
-        for line in reader.lines() {
-            let line = match line {
-                Ok(l) => l,
-                Err(_) => continue,
-            };
-            let line = line.trim();
-            if line.is_empty() {
+        // Primary file is truncated/corrupt (digest mismatch or unparsable) -
+        // fall back to the newest backup generation that still verifies.
+        for n in 1..=MAX_SESSION_BACKUPS {
+            let backup = backup_path(&path, n);
+            if !backup.exists() {
                 continue;
             }
+            let Ok(lock) = acquire_lock(&backup, false) else {
+                continue;
+            };
+            let loaded = load_session_file(&backup, key);
+            drop(lock);
+            if let Some((session, schema_version)) = loaded {
+                self.warn_on_schema_mismatch(&schema_version);
+                return Ok(Some(session));
+            }
+        }
 
-            let data: serde_json::Value = serde_json::from_str(line).map_err(|e| e.to_string())?;
+        Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "session file for '{}' is corrupt and no valid backup was found",
+            key
+        )))
+    }
+}
 
-            if data.get("_type").and_then(|v| v.as_str()) == Some("metadata") {
-                if let Some(meta) = data.get("metadata") {
-                    if let Some(obj) = meta.as_object() {
-                        for (k, v) in obj {
-                            metadata.insert(k.clone(), v.clone());
-                        }
-                    }
+/// Read and verify a single session file, returning `None` on any I/O
+/// error, parse failure, or digest mismatch rather than propagating - the
+/// caller treats that as "this generation isn't usable" and tries the
+/// next backup.
+///
+/// A file written purely by `save()` has exactly one digest line, at the
+/// end, covering every message. A file that has since had messages
+/// `append_message`'d onto it has more message lines *after* that digest
+/// - those aren't covered by any checkpoint, so they're trusted as-is
+/// (the append path trades the full-rewrite integrity guarantee for O(1)
+/// writes) and reported back via `dirty_since`.
+/// Parse a session file, returning the reconstructed `Session` alongside
+/// whatever schema version tag (see `SessionManager::set_schema`) was
+/// recorded in its metadata line, if any.
+fn load_session_file(path: &Path, key: &str) -> Option<(Session, Option<String>)> {
+    let file = File::open(path).ok()?;
+    let reader = BufReader::new(file);
+
+    let mut raw_lines = Vec::new();
+    for line in reader.lines() {
+        let line = line.ok()?;
+        let line = line.trim().to_string();
+        if !line.is_empty() {
+            raw_lines.push(line);
+        }
+    }
+    if raw_lines.is_empty() {
+        return None;
+    }
+
+    let meta_data: serde_json::Value = serde_json::from_str(&raw_lines[0]).ok()?;
+    if meta_data.get("_type").and_then(|v| v.as_str()) != Some("metadata") {
+        return None;
+    }
+
+    let mut messages = Vec::new();
+    let mut since_checkpoint: Vec<&str> = Vec::new();
+    let mut checkpoint_len: Option<usize> = None;
+    let mut updated_at = meta_data
+        .get("updated_at")
+        .and_then(|v| v.as_str())
+        .map(String::from);
+
+    for line in &raw_lines[1..] {
+        let value: serde_json::Value = serde_json::from_str(line).ok()?;
+        match value.get("_type").and_then(|v| v.as_str()) {
+            Some("digest") => {
+                let expected = value.get("sha256").and_then(|v| v.as_str())?;
+                if sha256_hex(&since_checkpoint.join("\n")) != expected {
+                    return None;
+                }
+                since_checkpoint.clear();
+                checkpoint_len = Some(messages.len());
+            }
+            Some("metadata_update") => {
+                if let Some(u) = value.get("updated_at").and_then(|v| v.as_str()) {
+                    updated_at = Some(u.to_string());
                 }
-                created_at = data
-                    .get("created_at")
-                    .and_then(|v| v.as_str())
-                    .map(String::from);
-            } else {
-                let msg: Message = serde_json::from_value(data).map_err(|e| e.to_string())?;
-                messages.push(msg);
+            }
+            _ => {
+                since_checkpoint.push(line);
+                messages.push(serde_json::from_value(value).ok()?);
             }
         }
+    }
 
-        let now = chrono::Utc::now()
-            .format("%Y-%m-%dT%H:%M:%S%.6f")
-            .to_string();
-
-        Ok(Some(Session {
+    let dirty_since = match checkpoint_len {
+        Some(n) if n < messages.len() => Some(n),
+        // No digest at all (a file only ever `append_message`'d to): no
+        // message is covered by a checkpoint yet.
+        None if !messages.is_empty() => Some(0),
+        _ => None,
+    };
+
+    let metadata = meta_data
+        .get("metadata")
+        .and_then(|v| v.as_object())
+        .map(|obj| obj.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default();
+    let created_at = meta_data
+        .get("created_at")
+        .and_then(|v| v.as_str())
+        .map(String::from);
+    let schema_version = meta_data
+        .get("schema_version")
+        .and_then(|v| v.as_str())
+        .map(String::from);
+
+    let now = chrono::Utc::now()
+        .format("%Y-%m-%dT%H:%M:%S%.6f")
+        .to_string();
+
+    Some((
+        Session {
             key: key.to_string(),
             messages,
             created_at: created_at.unwrap_or_else(|| now.clone()),
-            updated_at: now,
+            updated_at: updated_at.unwrap_or(now),
             metadata,
-        }))
+            dirty_since,
+            ephemeral: false,
+        },
+        schema_version,
+    ))
+}
+
+/// Nth backup generation of a session file (`n == 1` is the most recent),
+/// matching the `\.bak(\d)*$` suffix convention: `<file>.bak`,
+/// `<file>.bak2`, `<file>.bak3`, ...
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let suffix = if n <= 1 {
+        ".bak".to_string()
+    } else {
+        format!(".bak{}", n)
+    };
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Shift a session file's existing `.bak*` chain up one generation before
+/// it gets overwritten, dropping the oldest generation beyond
+/// `MAX_SESSION_BACKUPS`.
+fn rotate_backups(path: &Path) {
+    if !path.exists() {
+        return;
+    }
+    let _ = fs::remove_file(backup_path(path, MAX_SESSION_BACKUPS));
+    for n in (1..MAX_SESSION_BACKUPS).rev() {
+        let from = backup_path(path, n);
+        if from.exists() {
+            let _ = fs::rename(&from, backup_path(path, n + 1));
+        }
+    }
+    let _ = fs::rename(path, backup_path(path, 1));
+}
+
+/// Hex-encoded SHA-256 of `text`, used to detect a truncated/corrupt
+/// session file on load.
+fn sha256_hex(text: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(text.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// A single `SessionManager::search` match.
+struct SearchHit {
+    key: String,
+    message_index: usize,
+    role: String,
+    timestamp: String,
+    snippet: String,
+}
+
+/// Characters of context kept on each side of a match in `search_snippet`.
+const SEARCH_SNIPPET_CONTEXT: usize = 40;
+
+/// Build a short excerpt of `content` centered on the first case-insensitive
+/// occurrence of `needle_lower`, so a search hit is readable without
+/// opening the whole message.
+fn search_snippet(content: &str, needle_lower: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let lower_chars: Vec<char> = content.to_lowercase().chars().collect();
+    let needle_chars: Vec<char> = needle_lower.chars().collect();
+
+    let pos = if needle_chars.is_empty() {
+        None
+    } else {
+        lower_chars
+            .windows(needle_chars.len())
+            .position(|w| w == needle_chars.as_slice())
+    };
+
+    let Some(pos) = pos else {
+        let end = chars.len().min(SEARCH_SNIPPET_CONTEXT * 2);
+        return chars[..end].iter().collect();
+    };
+
+    let start = pos.saturating_sub(SEARCH_SNIPPET_CONTEXT);
+    let end = (pos + needle_chars.len() + SEARCH_SNIPPET_CONTEXT).min(chars.len());
+    let mut snippet: String = chars[start..end].iter().collect();
+    if start > 0 {
+        snippet = format!("...{}", snippet);
+    }
+    if end < chars.len() {
+        snippet.push_str("...");
     }
+    snippet
+}
+
+/// Path of the advisory lock file guarding `path` across processes.
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+/// Acquire an advisory lock on the file guarding `path`, retrying with
+/// backoff before giving up. The returned lock file holds the lock for
+/// as long as it is kept open - dropping it releases the lock.
+fn acquire_lock(path: &Path, exclusive: bool) -> PyResult<File> {
+    let lock_file = File::options()
+        .create(true)
+        .write(true)
+        .open(lock_path(path))
+        .map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!(
+                "Failed to open lock file for session: {}",
+                e
+            ))
+        })?;
+
+    for attempt in 0..LOCK_RETRY_ATTEMPTS {
+        let result = if exclusive {
+            lock_file.try_lock_exclusive()
+        } else {
+            lock_file.try_lock_shared()
+        };
+        if result.is_ok() {
+            return Ok(lock_file);
+        }
+        if attempt + 1 < LOCK_RETRY_ATTEMPTS {
+            thread::sleep(LOCK_RETRY_BACKOFF * (attempt + 1));
+        }
+    }
+
+    Err(PyErr::new::<pyo3::exceptions::PyBlockingIOError, _>(
+        format!(
+            "Could not acquire {} lock on session file {} after {} attempts",
+            if exclusive { "exclusive" } else { "shared" },
+            path.display(),
+            LOCK_RETRY_ATTEMPTS
+        ),
+    ))
 }
 
 /// Convert a string to a safe filename.
@@ -3,10 +3,13 @@
 use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyList};
 use regex::Regex;
+use rhai::{Engine, Scope, AST};
 use std::collections::HashMap;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// Loader for agent skills.
 ///
@@ -18,13 +21,28 @@ pub struct SkillsLoader {
     workspace: PathBuf,
     workspace_skills: PathBuf,
     builtin_skills: PathBuf,
+    /// Additional root directories searched between `workspace_skills` and
+    /// `builtin_skills`, in the given order.
+    extra_roots: Vec<PathBuf>,
+    /// Compiled `requires.when` predicates, keyed by skill name, so
+    /// `list_skills`/`check_requirements` don't recompile the script on
+    /// every call.
+    when_cache: Mutex<HashMap<String, Arc<AST>>>,
+    /// Detected version per binary name (`None` when a version couldn't be
+    /// determined), so repeated `list_skills` calls don't re-spawn the same
+    /// `--version` process.
+    version_cache: Mutex<HashMap<String, Option<String>>>,
 }
 
 #[pymethods]
 impl SkillsLoader {
     #[new]
-    #[pyo3(signature = (workspace, builtin_skills_dir=None))]
-    pub fn new(workspace: PathBuf, builtin_skills_dir: Option<PathBuf>) -> Self {
+    #[pyo3(signature = (workspace, builtin_skills_dir=None, extra_roots=None))]
+    pub fn new(
+        workspace: PathBuf,
+        builtin_skills_dir: Option<PathBuf>,
+        extra_roots: Option<Vec<PathBuf>>,
+    ) -> Self {
         let workspace_skills = workspace.join("skills");
 
         // Default builtin skills directory - relative to debot package
@@ -38,67 +56,37 @@ impl SkillsLoader {
             workspace,
             workspace_skills,
             builtin_skills,
+            extra_roots: extra_roots.unwrap_or_default(),
+            when_cache: Mutex::new(HashMap::new()),
+            version_cache: Mutex::new(HashMap::new()),
         }
     }
 
-    /// List all available skills.
+    /// List all available skills, discovered recursively under every root
+    /// (`workspace/skills`, any `extra_roots`, then the builtin dir) -
+    /// any directory containing a `SKILL.md` at any depth is a skill,
+    /// named by its path relative to its root (`git/commit`). Earlier
+    /// roots win on name collision.
     #[pyo3(signature = (filter_unavailable=true))]
     fn list_skills(&self, py: Python<'_>, filter_unavailable: bool) -> PyResult<Py<PyList>> {
         let result = PyList::empty(py);
         let mut seen_names: Vec<String> = Vec::new();
 
-        // Workspace skills (highest priority)
-        if self.workspace_skills.exists() {
-            if let Ok(entries) = fs::read_dir(&self.workspace_skills) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.is_dir() {
-                        let skill_file = path.join("SKILL.md");
-                        if skill_file.exists() {
-                            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                                let dict = PyDict::new(py);
-                                dict.set_item("name", name)?;
-                                dict.set_item("path", skill_file.to_string_lossy().to_string())?;
-                                dict.set_item("source", "workspace")?;
-
-                                if !filter_unavailable || self.check_requirements_for_skill(name) {
-                                    result.append(dict)?;
-                                    seen_names.push(name.to_string());
-                                }
-                            }
-                        }
-                    }
-                }
+        for (root, source) in self.ordered_roots() {
+            if !root.exists() {
+                continue;
             }
-        }
-
-        // Built-in skills
-        if !self.builtin_skills.as_os_str().is_empty() && self.builtin_skills.exists() {
-            if let Ok(entries) = fs::read_dir(&self.builtin_skills) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.is_dir() {
-                        let skill_file = path.join("SKILL.md");
-                        if skill_file.exists() {
-                            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                                if !seen_names.contains(&name.to_string()) {
-                                    let dict = PyDict::new(py);
-                                    dict.set_item("name", name)?;
-                                    dict.set_item(
-                                        "path",
-                                        skill_file.to_string_lossy().to_string(),
-                                    )?;
-                                    dict.set_item("source", "builtin")?;
-
-                                    if !filter_unavailable
-                                        || self.check_requirements_for_skill(name)
-                                    {
-                                        result.append(dict)?;
-                                    }
-                                }
-                            }
-                        }
-                    }
+            for (name, skill_file) in discover_skills_in_root(root) {
+                if seen_names.contains(&name) {
+                    continue;
+                }
+                if !filter_unavailable || self.check_requirements_for_skill(&name) {
+                    let dict = PyDict::new(py);
+                    dict.set_item("name", &name)?;
+                    dict.set_item("path", skill_file.to_string_lossy().to_string())?;
+                    dict.set_item("source", source)?;
+                    result.append(dict)?;
+                    seen_names.push(name);
                 }
             }
         }
@@ -106,41 +94,59 @@ impl SkillsLoader {
         Ok(result.into())
     }
 
-    /// Load a skill by name.
+    /// Load a skill by name, resolving nested names (`git/commit`) against
+    /// the same ordered roots `list_skills` discovers from.
     fn load_skill(&self, name: &str) -> Option<String> {
-        // Check workspace first
-        let workspace_skill = self.workspace_skills.join(name).join("SKILL.md");
-        if workspace_skill.exists() {
-            return fs::read_to_string(&workspace_skill).ok();
-        }
-
-        // Check built-in
-        if !self.builtin_skills.as_os_str().is_empty() {
-            let builtin_skill = self.builtin_skills.join(name).join("SKILL.md");
-            if builtin_skill.exists() {
-                return fs::read_to_string(&builtin_skill).ok();
+        let rel = PathBuf::from(name);
+        for (root, _) in self.ordered_roots() {
+            let skill_file = root.join(&rel).join("SKILL.md");
+            if skill_file.exists() {
+                return fs::read_to_string(&skill_file).ok();
             }
         }
-
         None
     }
 
-    /// Load specific skills for inclusion in agent context.
+    /// Load specific skills for inclusion in agent context, resolving
+    /// `requires.skills` transitively first so a skill's dependencies are
+    /// emitted before it. Dependencies are pulled in via a DFS topological
+    /// sort (white/gray/black marking); a cycle is broken deterministically
+    /// and reported with a `<!-- warning: skill cycle ... -->` comment
+    /// instead of recursing forever. A skill that fails its own
+    /// requirements is still emitted, annotated
+    /// `### Skill: X (unavailable: ...)`, so the agent knows the context
+    /// may be incomplete.
     pub fn load_skills_for_context(&self, skill_names: Vec<String>) -> String {
-        let mut parts = Vec::new();
+        let mut state: HashMap<String, u8> = HashMap::new();
+        let mut order: Vec<String> = Vec::new();
+        let mut warnings: Vec<String> = Vec::new();
+        let mut path: Vec<String> = Vec::new();
 
-        for name in skill_names {
-            if let Some(content) = self.load_skill(&name) {
+        for name in &skill_names {
+            self.visit_skill_dep(name, &mut state, &mut order, &mut warnings, &mut path);
+        }
+
+        let mut parts = Vec::new();
+        for name in &order {
+            if let Some(content) = self.load_skill(name) {
                 let stripped = strip_frontmatter(&content);
-                parts.push(format!("### Skill: {}\n\n{}", name, stripped));
+                let header = if self.check_requirements_for_skill(name) {
+                    format!("### Skill: {}", name)
+                } else {
+                    let meta = self.get_skill_meta(name);
+                    let missing = self.get_missing_requirements(name, &meta);
+                    format!("### Skill: {} (unavailable: {})", name, missing)
+                };
+                parts.push(format!("{}\n\n{}", header, stripped));
             }
         }
 
-        if parts.is_empty() {
-            String::new()
-        } else {
-            parts.join("\n\n---\n\n")
+        let mut sections = warnings;
+        if !parts.is_empty() {
+            sections.push(parts.join("\n\n---\n\n"));
         }
+
+        sections.join("\n\n")
     }
 
     /// Build a summary of all skills (name, description, path, availability).
@@ -167,7 +173,7 @@ impl SkillsLoader {
 
             let desc = self.get_skill_description(&name);
             let skill_meta = self.get_skill_meta(&name);
-            let available = self.check_requirements(&skill_meta);
+            let available = self.check_requirements(&name, &skill_meta);
 
             lines.push(format!(
                 "  <skill available=\"{}\">",
@@ -181,12 +187,30 @@ impl SkillsLoader {
             lines.push(format!("    <location>{}</location>", path));
 
             if !available {
-                let missing = self.get_missing_requirements(&skill_meta);
+                let missing = self.get_missing_requirements(&name, &skill_meta);
                 if !missing.is_empty() {
                     lines.push(format!("    <requires>{}</requires>", escape_xml(&missing)));
                 }
             }
 
+            let commands = self.get_skill_commands(&name);
+            if !commands.is_empty() {
+                let mut available_commands: Vec<&String> = commands
+                    .iter()
+                    .filter(|(_, cmd)| self.check_requirements(&name, &cmd.requirement_meta()))
+                    .map(|(cmd_name, _)| cmd_name)
+                    .collect();
+                available_commands.sort();
+                if !available_commands.is_empty() {
+                    let joined = available_commands
+                        .iter()
+                        .map(|s| s.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    lines.push(format!("    <commands>{}</commands>", escape_xml(&joined)));
+                }
+            }
+
             lines.push("  </skill>".to_string());
         }
 
@@ -221,6 +245,73 @@ impl SkillsLoader {
         Ok(result)
     }
 
+    /// Run a recipe from a skill's `commands` table, re-checking that
+    /// command's own `requires.bins`/`requires.env` before spawning it, then
+    /// appending `args` to the recipe's shell line. Returns
+    /// `{exit_code, stdout, stderr}`.
+    #[pyo3(signature = (name, command, args=None))]
+    fn run_skill_command(
+        &self,
+        py: Python<'_>,
+        name: &str,
+        command: &str,
+        args: Option<Vec<String>>,
+    ) -> PyResult<Py<PyDict>> {
+        let commands = self.get_skill_commands(name);
+        let recipe = commands.get(command).ok_or_else(|| {
+            pyo3::exceptions::PyKeyError::new_err(format!(
+                "skill '{}' has no command '{}'",
+                name, command
+            ))
+        })?;
+
+        let command_meta = recipe.requirement_meta();
+        if !self.check_requirements(name, &command_meta) {
+            let missing = self.get_missing_requirements(name, &command_meta);
+            return Err(pyo3::exceptions::PyRuntimeError::new_err(format!(
+                "command '{}' is unavailable: {}",
+                command, missing
+            )));
+        }
+
+        let args = args.unwrap_or_default();
+
+        // `args` are caller-supplied, untrusted data - splice them into the
+        // shell string and `;`, `$( )`, backticks etc. in an arg become
+        // arbitrary commands. Pass them as literal argv entries after `--`
+        // instead, so `sh` never re-parses them.
+        let mut cmd = if cfg!(target_os = "windows") {
+            let mut full_command = recipe.cmd.clone();
+            for arg in &args {
+                full_command.push(' ');
+                full_command.push_str(arg);
+            }
+            let mut c = std::process::Command::new("cmd");
+            c.args(["/C", &full_command]);
+            c
+        } else {
+            let mut c = std::process::Command::new("sh");
+            c.arg("-c")
+                .arg(format!("{} \"$@\"", recipe.cmd))
+                .arg("--")
+                .args(&args);
+            c
+        };
+
+        let output = cmd.output().map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!(
+                "failed to run command '{}': {}",
+                command, e
+            ))
+        })?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("exit_code", output.status.code().unwrap_or(-1))?;
+        dict.set_item("stdout", String::from_utf8_lossy(&output.stdout).to_string())?;
+        dict.set_item("stderr", String::from_utf8_lossy(&output.stderr).to_string())?;
+        Ok(dict.into())
+    }
+
     /// Get metadata from a skill's frontmatter.
     fn get_skill_metadata(&self, name: &str) -> Option<HashMap<String, String>> {
         let content = self.load_skill(name)?;
@@ -271,18 +362,100 @@ impl SkillsLoader {
         HashMap::new()
     }
 
+    /// Get a skill's `commands` table (recipe name -> shell line, with
+    /// optional per-command requirements), parsed from its frontmatter's
+    /// `commands` key.
+    fn get_skill_commands(&self, name: &str) -> HashMap<String, SkillCommand> {
+        if let Some(meta) = self.get_skill_metadata(name) {
+            if let Some(commands_str) = meta.get("commands") {
+                return parse_skill_commands(commands_str);
+            }
+        }
+        HashMap::new()
+    }
+
+    /// Root directories to search, in precedence order: workspace skills
+    /// first, then any configured `extra_roots`, then the builtin dir
+    /// last (omitted if unset).
+    fn ordered_roots(&self) -> Vec<(&PathBuf, &'static str)> {
+        let mut roots = vec![(&self.workspace_skills, "workspace")];
+        for extra in &self.extra_roots {
+            roots.push((extra, "extra"));
+        }
+        if !self.builtin_skills.as_os_str().is_empty() {
+            roots.push((&self.builtin_skills, "builtin"));
+        }
+        roots
+    }
+
+    /// DFS step for `load_skills_for_context`'s dependency resolution.
+    /// `state` marks each skill white (unvisited, the default), gray (on
+    /// the current DFS path), or black (fully emitted); `path` is the
+    /// current DFS stack, used to describe a cycle if one is found.
+    fn visit_skill_dep(
+        &self,
+        name: &str,
+        state: &mut HashMap<String, u8>,
+        order: &mut Vec<String>,
+        warnings: &mut Vec<String>,
+        path: &mut Vec<String>,
+    ) {
+        match state.get(name) {
+            Some(1) => {
+                if let Some(pos) = path.iter().position(|n| n == name) {
+                    let mut cycle = path[pos..].to_vec();
+                    cycle.push(name.to_string());
+                    warnings.push(format!(
+                        "<!-- warning: skill cycle {} -->",
+                        cycle.join(" -> ")
+                    ));
+                }
+                return;
+            }
+            Some(2) => return,
+            _ => {}
+        }
+
+        state.insert(name.to_string(), 1);
+        path.push(name.to_string());
+
+        let skill_meta = self.get_skill_meta(name);
+        if let Some(deps) = skill_meta.get("requires.skills") {
+            for dep in deps.split(',').map(|s| s.trim()) {
+                if !dep.is_empty() {
+                    self.visit_skill_dep(dep, state, order, warnings, path);
+                }
+            }
+        }
+
+        path.pop();
+        state.insert(name.to_string(), 2);
+        order.push(name.to_string());
+    }
+
     fn check_requirements_for_skill(&self, name: &str) -> bool {
         let skill_meta = self.get_skill_meta(name);
-        self.check_requirements(&skill_meta)
+        self.check_requirements(name, &skill_meta)
     }
 
-    fn check_requirements(&self, skill_meta: &HashMap<String, String>) -> bool {
-        // Check bins
+    fn check_requirements(&self, name: &str, skill_meta: &HashMap<String, String>) -> bool {
+        // Check bins, each optionally carrying a semver constraint
+        // (`node>=18.0`).
         if let Some(bins) = skill_meta.get("requires.bins") {
-            for bin in bins.split(',').map(|s| s.trim()) {
-                if !bin.is_empty() && !command_exists(bin) {
+            for entry in bins.split(',').map(|s| s.trim()) {
+                if entry.is_empty() {
+                    continue;
+                }
+                let (bin, constraint) = parse_bin_requirement(entry);
+                if !command_exists(&bin) {
                     return false;
                 }
+                if let Some(constraint) = &constraint {
+                    match self.cached_bin_version(&bin) {
+                        Some(version) if version_satisfies(&version, constraint) => {}
+                        _ => return false,
+                    }
+                }
             }
         }
 
@@ -295,16 +468,41 @@ impl SkillsLoader {
             }
         }
 
+        // Check the arbitrary Rhai predicate, if any.
+        if let Some(expr) = skill_meta.get("requires.when") {
+            if !expr.trim().is_empty() && !self.eval_when(name, expr) {
+                return false;
+            }
+        }
+
         true
     }
 
-    fn get_missing_requirements(&self, skill_meta: &HashMap<String, String>) -> String {
+    fn get_missing_requirements(&self, name: &str, skill_meta: &HashMap<String, String>) -> String {
         let mut missing = Vec::new();
 
         if let Some(bins) = skill_meta.get("requires.bins") {
-            for bin in bins.split(',').map(|s| s.trim()) {
-                if !bin.is_empty() && !command_exists(bin) {
-                    missing.push(format!("CLI: {}", bin));
+            for entry in bins.split(',').map(|s| s.trim()) {
+                if entry.is_empty() {
+                    continue;
+                }
+                let (bin, constraint) = parse_bin_requirement(entry);
+                if !command_exists(&bin) {
+                    missing.push(format!("CLI: {} (not found)", bin));
+                    continue;
+                }
+                if let Some(constraint) = &constraint {
+                    match self.cached_bin_version(&bin) {
+                        Some(version) if version_satisfies(&version, constraint) => {}
+                        Some(version) => missing.push(format!(
+                            "CLI: {} (found {}, needs {})",
+                            bin, version, constraint
+                        )),
+                        None => missing.push(format!(
+                            "CLI: {} (version undetermined, needs {})",
+                            bin, constraint
+                        )),
+                    }
                 }
             }
         }
@@ -317,8 +515,111 @@ impl SkillsLoader {
             }
         }
 
+        if let Some(expr) = skill_meta.get("requires.when") {
+            if !expr.trim().is_empty() && !self.eval_when(name, expr) {
+                missing.push(format!("SCRIPT: {} evaluated false", expr));
+            }
+        }
+
         missing.join(", ")
     }
+
+    /// Evaluate a skill's `requires.when` Rhai predicate, compiling and
+    /// caching the AST by skill name on first use. A script error or a
+    /// non-`true` result both count as "unavailable" - `requires.when` is
+    /// a gate, not a general-purpose computation.
+    fn eval_when(&self, name: &str, expr: &str) -> bool {
+        let ast = {
+            let mut cache = self.when_cache.lock().unwrap();
+            if let Some(ast) = cache.get(name) {
+                ast.clone()
+            } else {
+                let engine = build_skill_engine();
+                match engine.compile(expr) {
+                    Ok(ast) => {
+                        let ast = Arc::new(ast);
+                        cache.insert(name.to_string(), ast.clone());
+                        ast
+                    }
+                    Err(_) => return false,
+                }
+            }
+        };
+
+        let engine = build_skill_engine();
+        let mut scope = Scope::new();
+        engine
+            .eval_ast_with_scope::<bool>(&mut scope, &ast)
+            .unwrap_or(false)
+    }
+
+    /// Detected version of `bin`, spawning `--version`/`-V` at most once
+    /// per loader instance regardless of how many skills require it.
+    fn cached_bin_version(&self, bin: &str) -> Option<String> {
+        if let Some(cached) = self.version_cache.lock().unwrap().get(bin) {
+            return cached.clone();
+        }
+        let detected = detect_bin_version(bin);
+        self.version_cache
+            .lock()
+            .unwrap()
+            .insert(bin.to_string(), detected.clone());
+        detected
+    }
+}
+
+/// Build a Rhai engine exposing only the host functions `requires.when`
+/// predicates need, with operations capped so a malformed predicate can't
+/// hang skill discovery, and no file/module imports.
+fn build_skill_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(500_000);
+    engine.disable_symbol("import");
+
+    engine.register_fn("command_exists", |name: &str| -> bool { command_exists(name) });
+    engine.register_fn("env", |name: &str| -> String { env::var(name).unwrap_or_default() });
+    engine.register_fn("has_env", |name: &str| -> bool { env::var(name).is_ok() });
+    engine.register_fn("file_exists", |path: &str| -> bool { PathBuf::from(path).exists() });
+    engine.register_fn("is_file", |path: &str| -> bool { PathBuf::from(path).is_file() });
+    engine.register_fn("is_dir", |path: &str| -> bool { PathBuf::from(path).is_dir() });
+
+    engine
+}
+
+/// Recursively find every directory under `root` containing a `SKILL.md`,
+/// returning `(name, skill_file_path)` pairs where `name` is the
+/// directory's path relative to `root` with separators normalized to `/`
+/// (so `skills/git/commit/SKILL.md` becomes `git/commit`).
+fn discover_skills_in_root(root: &Path) -> Vec<(String, PathBuf)> {
+    let mut found = Vec::new();
+    walk_skills_dir(root, root, &mut found);
+    found
+}
+
+fn walk_skills_dir(root: &Path, dir: &Path, found: &mut Vec<(String, PathBuf)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let skill_file = path.join("SKILL.md");
+        if skill_file.exists() {
+            if let Ok(rel) = path.strip_prefix(root) {
+                let name = rel
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().to_string())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                if !name.is_empty() {
+                    found.push((name, skill_file));
+                }
+            }
+        }
+        walk_skills_dir(root, &path, found);
+    }
 }
 
 /// Strip YAML frontmatter from markdown content.
@@ -354,6 +655,178 @@ fn parse_debot_metadata(raw: &str) -> HashMap<String, String> {
     result
 }
 
+/// A single entry from a skill's `commands` frontmatter table: a shell line
+/// plus its own optional `requires.bins`/`requires.env`, checked separately
+/// from the skill's own requirements when the command is actually invoked.
+struct SkillCommand {
+    cmd: String,
+    requires_bins: Option<String>,
+    requires_env: Option<String>,
+}
+
+impl SkillCommand {
+    /// Build a synthetic `skill_meta`-shaped map carrying just this
+    /// command's requirements, so `check_requirements`/
+    /// `get_missing_requirements` can be reused as-is.
+    fn requirement_meta(&self) -> HashMap<String, String> {
+        let mut meta = HashMap::new();
+        if let Some(bins) = &self.requires_bins {
+            meta.insert("requires.bins".to_string(), bins.clone());
+        }
+        if let Some(envs) = &self.requires_env {
+            meta.insert("requires.env".to_string(), envs.clone());
+        }
+        meta
+    }
+}
+
+/// Parse a skill's `commands` frontmatter value: a JSON object mapping
+/// command name to either a bare shell line (`"commit": "git commit"`) or
+/// an object carrying the shell line plus requirements
+/// (`"amend": {"cmd": "...", "requires.bins": "git"}`). Malformed or
+/// unrecognized entries are skipped rather than failing the whole skill.
+fn parse_skill_commands(raw: &str) -> HashMap<String, SkillCommand> {
+    let mut result = HashMap::new();
+
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(raw) else {
+        return result;
+    };
+    let Some(obj) = value.as_object() else {
+        return result;
+    };
+
+    for (name, entry) in obj {
+        let command = match entry {
+            serde_json::Value::String(cmd) => SkillCommand {
+                cmd: cmd.clone(),
+                requires_bins: None,
+                requires_env: None,
+            },
+            serde_json::Value::Object(fields) => SkillCommand {
+                cmd: fields
+                    .get("cmd")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                requires_bins: fields
+                    .get("requires.bins")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+                requires_env: fields
+                    .get("requires.env")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+            },
+            _ => continue,
+        };
+
+        if !command.cmd.is_empty() {
+            result.insert(name.clone(), command);
+        }
+    }
+
+    result
+}
+
+/// Split a `requires.bins` entry like `node>=18.0` into its binary name
+/// and optional semver constraint (`>=18.0`), or `(name, None)` for a
+/// bare binary name like `jq`.
+fn parse_bin_requirement(entry: &str) -> (String, Option<String>) {
+    let entry = entry.trim();
+    let re = Regex::new(r"(<=|>=|==|\^|~|<|>|=)").unwrap();
+    match re.find(entry) {
+        Some(m) => (
+            entry[..m.start()].trim().to_string(),
+            Some(entry[m.start()..].trim().to_string()),
+        ),
+        None => (entry.to_string(), None),
+    }
+}
+
+/// How long `detect_bin_version` waits for a `--version`/`-V` child before
+/// killing it and giving up on that flag, so a hung binary (or one that's
+/// actually a wrapper blocking on stdin) can't stall skill discovery
+/// indefinitely.
+const BIN_VERSION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Run `bin --version` (falling back to `-V`) and pull the first
+/// `\d+.\d+(.\d+)?` substring out of its combined stdout+stderr, or
+/// `None` if neither flag produced something that looks like a version.
+fn detect_bin_version(bin: &str) -> Option<String> {
+    use std::io::Read;
+    use std::process::Stdio;
+
+    let version_re = Regex::new(r"\d+\.\d+(?:\.\d+)?").unwrap();
+    for flag in ["--version", "-V"] {
+        let Ok(mut child) = std::process::Command::new(bin)
+            .arg(flag)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+        else {
+            continue;
+        };
+
+        let start = Instant::now();
+        let exited = loop {
+            match child.try_wait() {
+                Ok(Some(_)) => break true,
+                Ok(None) => {
+                    if start.elapsed() >= BIN_VERSION_TIMEOUT {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        break false;
+                    }
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+                Err(_) => break false,
+            }
+        };
+        if !exited {
+            continue;
+        }
+
+        let mut stdout = String::new();
+        let mut stderr = String::new();
+        if let Some(mut out) = child.stdout.take() {
+            let _ = out.read_to_string(&mut stdout);
+        }
+        if let Some(mut err) = child.stderr.take() {
+            let _ = err.read_to_string(&mut stderr);
+        }
+        let combined = format!("{}{}", stdout, stderr);
+        if let Some(m) = version_re.find(&combined) {
+            return Some(m.as_str().to_string());
+        }
+    }
+    None
+}
+
+/// Pad a detected `major.minor` or `major.minor.patch` string to full
+/// semver so `semver::Version::parse` accepts it.
+fn normalize_semver(raw: &str) -> String {
+    match raw.matches('.').count() {
+        0 => format!("{}.0.0", raw),
+        1 => format!("{}.0", raw),
+        _ => raw.to_string(),
+    }
+}
+
+/// Whether `detected` (a bare `major.minor[.patch]` version string)
+/// satisfies `constraint` (a `semver::VersionReq` expression like
+/// `>=18.0`).
+fn version_satisfies(detected: &str, constraint: &str) -> bool {
+    let req = match semver::VersionReq::parse(constraint) {
+        Ok(r) => r,
+        Err(_) => return false,
+    };
+    match semver::Version::parse(&normalize_semver(detected)) {
+        Ok(version) => req.matches(&version),
+        Err(_) => false,
+    }
+}
+
 /// Check if a command exists in PATH.
 fn command_exists(cmd: &str) -> bool {
     if let Ok(path) = env::var("PATH") {
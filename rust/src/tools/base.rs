@@ -3,6 +3,7 @@
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 /// Wrapper for PyObject to make it Clone-able.
@@ -56,6 +57,183 @@ impl ToolSchema {
     }
 }
 
+/// Declared conversion for a typed tool parameter.
+///
+/// Tools register these per parameter name via [`Tool::conversions`] so
+/// `ToolRegistry::execute` can coerce the raw Python argument into a
+/// [`TypedValue`] before the tool ever sees it, instead of flattening
+/// everything to a string.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Conversion {
+    String,
+    Bytes,
+    Integer,
+    Float,
+    Boolean,
+    Timestamp,
+    TimestampFmt(String),
+}
+
+impl Conversion {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Conversion::String => "string",
+            Conversion::Bytes => "bytes",
+            Conversion::Integer => "integer",
+            Conversion::Float => "float",
+            Conversion::Boolean => "boolean",
+            Conversion::Timestamp | Conversion::TimestampFmt(_) => "timestamp",
+        }
+    }
+}
+
+/// A tool-call parameter value already parsed into its declared type.
+#[derive(Clone, Debug)]
+pub enum TypedValue {
+    String(String),
+    Bytes(Vec<u8>),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Timestamp(f64),
+}
+
+impl TypedValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            TypedValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            TypedValue::Integer(i) => Some(*i),
+            TypedValue::Float(f) => Some(*f as i64),
+            TypedValue::Timestamp(t) => Some(*t as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            TypedValue::Float(f) => Some(*f),
+            TypedValue::Integer(i) => Some(*i as f64),
+            TypedValue::Timestamp(t) => Some(*t),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            TypedValue::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            TypedValue::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Render back to a plain string, for tools that just want display text.
+    pub fn to_display_string(&self) -> String {
+        match self {
+            TypedValue::String(s) => s.clone(),
+            TypedValue::Bytes(b) => String::from_utf8_lossy(b).to_string(),
+            TypedValue::Integer(i) => i.to_string(),
+            TypedValue::Float(f) => f.to_string(),
+            TypedValue::Boolean(b) => b.to_string(),
+            TypedValue::Timestamp(t) => t.to_string(),
+        }
+    }
+
+    /// Parse a raw Python argument according to `conversion`.
+    ///
+    /// Returns a structured `parameter '<key>' expected <type>` message on
+    /// failure so callers can surface `Error: {msg}` to the model.
+    pub fn coerce(
+        key: &str,
+        value: &Bound<'_, PyAny>,
+        conversion: &Conversion,
+    ) -> Result<TypedValue, String> {
+        let fail = || format!("parameter '{}' expected {}", key, conversion.type_name());
+        match conversion {
+            Conversion::String => value
+                .extract::<String>()
+                .or_else(|_| {
+                    // A dict (e.g. `exec`'s `env` object) is passed on as JSON text.
+                    if value.is_instance_of::<pyo3::types::PyDict>() {
+                        value.py().import("json")?.call_method1("dumps", (value,))?.extract::<String>()
+                    } else {
+                        value.str().map(|s| s.to_string())
+                    }
+                })
+                .map(TypedValue::String)
+                .map_err(|_| fail()),
+            Conversion::Bytes => {
+                if let Ok(b) = value.extract::<Vec<u8>>() {
+                    Ok(TypedValue::Bytes(b))
+                } else if let Ok(s) = value.extract::<String>() {
+                    Ok(TypedValue::Bytes(s.into_bytes()))
+                } else {
+                    Err(fail())
+                }
+            }
+            Conversion::Integer => {
+                if let Ok(i) = value.extract::<i64>() {
+                    Ok(TypedValue::Integer(i))
+                } else if let Ok(s) = value.extract::<String>() {
+                    s.trim().parse::<i64>().map(TypedValue::Integer).map_err(|_| fail())
+                } else {
+                    Err(fail())
+                }
+            }
+            Conversion::Float => {
+                if let Ok(f) = value.extract::<f64>() {
+                    Ok(TypedValue::Float(f))
+                } else if let Ok(s) = value.extract::<String>() {
+                    s.trim().parse::<f64>().map(TypedValue::Float).map_err(|_| fail())
+                } else {
+                    Err(fail())
+                }
+            }
+            Conversion::Boolean => {
+                if let Ok(b) = value.extract::<bool>() {
+                    Ok(TypedValue::Boolean(b))
+                } else if let Ok(s) = value.extract::<String>() {
+                    match s.trim().to_lowercase().as_str() {
+                        "true" | "1" | "yes" => Ok(TypedValue::Boolean(true)),
+                        "false" | "0" | "no" => Ok(TypedValue::Boolean(false)),
+                        _ => Err(fail()),
+                    }
+                } else {
+                    Err(fail())
+                }
+            }
+            Conversion::Timestamp => Self::parse_timestamp(value, None).ok_or_else(fail),
+            Conversion::TimestampFmt(fmt) => {
+                Self::parse_timestamp(value, Some(fmt)).ok_or_else(fail)
+            }
+        }
+    }
+
+    fn parse_timestamp(value: &Bound<'_, PyAny>, fmt: Option<&str>) -> Option<TypedValue> {
+        if let Ok(f) = value.extract::<f64>() {
+            return Some(TypedValue::Timestamp(f));
+        }
+        let s = value.extract::<String>().ok()?;
+        if let Some(fmt) = fmt {
+            let dt = chrono::NaiveDateTime::parse_from_str(&s, fmt).ok()?;
+            return Some(TypedValue::Timestamp(dt.and_utc().timestamp() as f64));
+        }
+        let dt = chrono::DateTime::parse_from_rfc3339(&s).ok()?;
+        Some(TypedValue::Timestamp(dt.timestamp() as f64))
+    }
+}
+
 /// Trait for tools - implemented by each concrete tool type.
 ///
 /// In PyO3, we can't use Rust traits directly with Python, so we use
@@ -65,6 +243,12 @@ pub trait Tool: Send + Sync {
     fn description(&self) -> &str;
     fn parameters(&self) -> HashMap<String, serde_json::Value>;
 
+    /// Declared [`Conversion`] per parameter name. Parameters with no entry
+    /// are coerced as plain strings, matching the historical behavior.
+    fn conversions(&self) -> HashMap<String, Conversion> {
+        HashMap::new()
+    }
+
     fn to_schema(&self, py: Python<'_>) -> PyResult<ToolSchema> {
         let params = serde_json::to_string(&self.parameters())
             .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
@@ -82,6 +266,11 @@ pub trait Tool: Send + Sync {
     }
 }
 
+/// Sender a tool uses to report incremental progress chunks (e.g. exec
+/// stdout/stderr lines, bytes-written counts) back to the caller while it
+/// is still running, independent of the final result it eventually returns.
+pub type ChunkSender = tokio::sync::mpsc::UnboundedSender<String>;
+
 /// Helper to create a standard JSON Schema object type.
 pub fn object_schema(
     properties: HashMap<String, serde_json::Value>,
@@ -110,3 +299,75 @@ pub fn int_prop(description: &str) -> serde_json::Value {
         "description": description
     })
 }
+
+/// Resolve `candidate` against an optional sandbox `root`, rejecting any
+/// path that would escape it. With `root` set to `None` (the historical,
+/// unsandboxed default) the candidate is returned unchanged.
+///
+/// `candidate` need not exist yet (e.g. a `write_file` target): this walks
+/// up to the nearest existing ancestor, canonicalizes that, and rejoins the
+/// non-existent suffix, so symlink and `..` tricks can't be used to land
+/// outside `root` even before the file itself is created.
+pub async fn resolve_within_root(
+    root: Option<&Path>,
+    candidate: &Path,
+) -> Result<PathBuf, String> {
+    let root = match root {
+        Some(r) => r,
+        None => return Ok(candidate.to_path_buf()),
+    };
+
+    let canonical_root = tokio::fs::canonicalize(root)
+        .await
+        .map_err(|e| format!("Error: invalid workspace root: {}", e))?;
+
+    let candidate = if candidate.is_absolute() {
+        candidate.to_path_buf()
+    } else {
+        root.join(candidate)
+    };
+
+    let (existing, rest) = existing_ancestor(&candidate).await;
+    let canonical_existing = tokio::fs::canonicalize(&existing)
+        .await
+        .map_err(|e| format!("Error: {}", e))?;
+
+    let resolved = if rest.as_os_str().is_empty() {
+        canonical_existing
+    } else {
+        canonical_existing.join(rest)
+    };
+
+    if resolved.starts_with(&canonical_root) {
+        Ok(resolved)
+    } else {
+        Err("Error: path escapes workspace root".to_string())
+    }
+}
+
+/// Split `path` into its nearest existing ancestor plus the (possibly
+/// empty) remaining suffix that doesn't exist yet.
+async fn existing_ancestor(path: &Path) -> (PathBuf, PathBuf) {
+    let mut existing = path.to_path_buf();
+    let mut rest = PathBuf::new();
+
+    loop {
+        if tokio::fs::metadata(&existing).await.is_ok() {
+            return (existing, rest);
+        }
+
+        let Some(name) = existing.file_name().map(|n| n.to_os_string()) else {
+            return (existing, rest);
+        };
+
+        let mut new_rest = PathBuf::from(name);
+        if !rest.as_os_str().is_empty() {
+            new_rest.push(&rest);
+        }
+        rest = new_rest;
+
+        if !existing.pop() {
+            return (existing, rest);
+        }
+    }
+}
@@ -1,12 +1,15 @@
-//! File system tools: read, write, edit, list directory.
+//! File system tools: read, write, edit, list directory, search.
 
 use pyo3::prelude::*;
 use pyo3_async_runtimes::tokio::future_into_py;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 
-use super::base::{object_schema, string_prop, Tool, ToolSchema};
+use super::base::{
+    int_prop, object_schema, resolve_within_root, string_prop, ChunkSender, Conversion, Tool,
+    ToolSchema, TypedValue,
+};
 
 /// Expand ~ to home directory.
 fn expand_path(path: &str) -> PathBuf {
@@ -18,14 +21,133 @@ fn expand_path(path: &str) -> PathBuf {
     PathBuf::from(path)
 }
 
+/// Sibling temp path used for atomic writes, e.g. `notes.txt` -> `notes.txt.tmp`.
+fn tmp_path_for(file_path: &Path) -> PathBuf {
+    let name = file_path.file_name().unwrap_or_default().to_string_lossy();
+    file_path.with_file_name(format!("{}.tmp", name))
+}
+
+/// Detect the dominant line ending already used by an existing file, so a
+/// write can preserve it instead of silently converting `\r\n` to `\n` (or
+/// vice versa). Returns `None` if the file doesn't exist yet.
+async fn detect_existing_line_ending(file_path: &Path) -> Option<&'static str> {
+    let bytes = fs::read(file_path).await.ok()?;
+    if bytes.windows(2).any(|w| w == b"\r\n") {
+        Some("\r\n")
+    } else {
+        Some("\n")
+    }
+}
+
+/// Normalize `content` to `target`'s line ending ("\n" or "\r\n"),
+/// collapsing any existing `\r\n` first so mixed endings don't double up.
+fn normalize_line_endings(content: &str, target: &str) -> String {
+    let unified = content.replace("\r\n", "\n");
+    if target == "\r\n" {
+        unified.replace('\n', "\r\n")
+    } else {
+        unified
+    }
+}
+
+/// Atomically write `content` to `file_path` via a same-directory temp file
+/// plus rename, so a crash or concurrent reader never observes a partially
+/// written file. Reports progress through `on_chunk` (if given) in the same
+/// `wrote X of Y bytes to {path}` format `write_file` has always used.
+async fn atomic_write(
+    file_path: &Path,
+    content: &[u8],
+    path: &str,
+    on_chunk: Option<&ChunkSender>,
+) -> Result<(), String> {
+    use tokio::io::AsyncWriteExt;
+
+    let tmp_path = tmp_path_for(file_path);
+    let total = content.len();
+
+    let mut file = match fs::File::create(&tmp_path).await {
+        Ok(f) => f,
+        Err(e) => {
+            return Err(if e.kind() == std::io::ErrorKind::PermissionDenied {
+                format!("Error: Permission denied: {}", path)
+            } else {
+                format!("Error writing file: {}", e)
+            });
+        }
+    };
+
+    const CHUNK_LEN: usize = 64 * 1024;
+    let mut written = 0usize;
+    for slice in content.chunks(CHUNK_LEN) {
+        if let Err(e) = file.write_all(slice).await {
+            let _ = fs::remove_file(&tmp_path).await;
+            return Err(format!("Error writing file: {}", e));
+        }
+        written += slice.len();
+        if let Some(tx) = on_chunk {
+            let _ = tx.send(format!("wrote {} of {} bytes to {}", written, total, path));
+        }
+    }
+
+    if let Err(e) = file.flush().await {
+        let _ = fs::remove_file(&tmp_path).await;
+        return Err(format!("Error writing file: {}", e));
+    }
+    drop(file);
+
+    if let Err(e) = fs::rename(&tmp_path, file_path).await {
+        let _ = fs::remove_file(&tmp_path).await;
+        return Err(format!("Error writing file: {}", e));
+    }
+
+    Ok(())
+}
+
+/// Read `file_path` line-by-line, collecting only the 1-indexed window
+/// `[offset, offset + limit - 1]` (or `[offset, end]` if `limit` is `None`)
+/// while still counting every line so the caller can report how many lines
+/// the file has in total, without ever holding the whole file as one string.
+async fn read_lines_windowed(
+    file_path: &Path,
+    offset: usize,
+    limit: Option<usize>,
+    with_line_numbers: bool,
+) -> std::io::Result<(Vec<String>, usize)> {
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    let file = fs::File::open(file_path).await?;
+    let mut lines = BufReader::new(file).lines();
+    let end = limit.map(|l| offset + l.saturating_sub(1));
+
+    let mut collected = Vec::new();
+    let mut total = 0usize;
+    while let Some(line) = lines.next_line().await? {
+        total += 1;
+        if total >= offset && end.map(|e| total <= e).unwrap_or(true) {
+            if with_line_numbers {
+                collected.push(format!("{:>6}\t{}", total, line));
+            } else {
+                collected.push(line);
+            }
+        }
+    }
+
+    Ok((collected, total))
+}
+
 // ============================================================================
 // ReadFileTool
 // ============================================================================
 
 /// Tool to read file contents.
+///
+/// When constructed with a `root`, every path is confined to that
+/// directory; paths that resolve outside it are rejected instead of read.
 #[pyclass]
 #[derive(Clone)]
-pub struct ReadFileTool;
+pub struct ReadFileTool {
+    root: Option<PathBuf>,
+}
 
 impl Tool for ReadFileTool {
     fn name(&self) -> &str {
@@ -39,8 +161,31 @@ impl Tool for ReadFileTool {
     fn parameters(&self) -> HashMap<String, serde_json::Value> {
         let mut props = HashMap::new();
         props.insert("path".into(), string_prop("The file path to read"));
+        props.insert(
+            "offset".into(),
+            int_prop("1-indexed line number to start reading from (default 1)"),
+        );
+        props.insert(
+            "limit".into(),
+            int_prop("Maximum number of lines to read (reads to end of file if omitted)"),
+        );
+        props.insert(
+            "with_line_numbers".into(),
+            serde_json::json!({
+                "type": "boolean",
+                "description": "Prefix each line with its 1-indexed line number, cat -n style (default false)"
+            }),
+        );
         object_schema(props, vec!["path"])
     }
+
+    fn conversions(&self) -> HashMap<String, Conversion> {
+        let mut map = HashMap::new();
+        map.insert("offset".to_string(), Conversion::Integer);
+        map.insert("limit".to_string(), Conversion::Integer);
+        map.insert("with_line_numbers".to_string(), Conversion::Boolean);
+        map
+    }
 }
 
 impl ReadFileTool {
@@ -52,34 +197,93 @@ impl ReadFileTool {
         Tool::to_schema(self, py)
     }
 
-    pub async fn execute_inner(&self, params: &HashMap<String, String>) -> String {
-        let path = match params.get("path") {
+    pub fn conversions(&self) -> HashMap<String, Conversion> {
+        Tool::conversions(self)
+    }
+
+    pub async fn execute_inner(
+        &self,
+        params: &HashMap<String, TypedValue>,
+        _on_chunk: Option<ChunkSender>,
+    ) -> String {
+        let path = match params.get("path").and_then(|v| v.as_str()) {
             Some(p) => p,
             None => return "Error: Missing required parameter 'path'".to_string(),
         };
 
         let file_path = expand_path(path);
+        let file_path = match resolve_within_root(self.root.as_deref(), &file_path).await {
+            Ok(p) => p,
+            Err(e) => return e,
+        };
+
+        let offset = params
+            .get("offset")
+            .and_then(|v| v.as_i64())
+            .map(|n| n.max(1) as usize)
+            .unwrap_or(1);
+        let limit = params
+            .get("limit")
+            .and_then(|v| v.as_i64())
+            .map(|n| n.max(0) as usize);
+        let with_line_numbers = params
+            .get("with_line_numbers")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        if offset == 1 && limit.is_none() && !with_line_numbers {
+            return match fs::read_to_string(&file_path).await {
+                Ok(content) => content,
+                Err(e) => {
+                    if e.kind() == std::io::ErrorKind::NotFound {
+                        format!("Error: File not found: {}", path)
+                    } else if e.kind() == std::io::ErrorKind::PermissionDenied {
+                        format!("Error: Permission denied: {}", path)
+                    } else {
+                        format!("Error reading file: {}", e)
+                    }
+                }
+            };
+        }
 
-        match fs::read_to_string(&file_path).await {
-            Ok(content) => content,
+        let (lines, total) = match read_lines_windowed(&file_path, offset, limit, with_line_numbers).await {
+            Ok(result) => result,
             Err(e) => {
-                if e.kind() == std::io::ErrorKind::NotFound {
+                return if e.kind() == std::io::ErrorKind::NotFound {
                     format!("Error: File not found: {}", path)
                 } else if e.kind() == std::io::ErrorKind::PermissionDenied {
                     format!("Error: Permission denied: {}", path)
                 } else {
                     format!("Error reading file: {}", e)
-                }
+                };
             }
+        };
+
+        if lines.is_empty() {
+            return format!(
+                "File {} has no lines at offset {} ({} lines total)",
+                path, offset, total
+            );
+        }
+
+        let shown_end = offset + lines.len() - 1;
+        let mut result = lines.join("\n");
+        if offset > 1 || shown_end < total {
+            result.push_str(&format!(
+                "\n... (showing lines {}-{} of {})",
+                offset, shown_end, total
+            ));
         }
+        result
     }
 }
 
 #[pymethods]
 impl ReadFileTool {
     #[new]
-    fn new() -> Self {
-        Self
+    #[pyo3(signature = (root=None))]
+    fn new(root: Option<PathBuf>) -> Self {
+        Self { root }
     }
 
     #[getter]
@@ -101,12 +305,30 @@ impl ReadFileTool {
         Ok(result.into())
     }
 
-    fn execute<'py>(&self, py: Python<'py>, path: String) -> PyResult<Bound<'py, PyAny>> {
+    #[pyo3(signature = (path, offset=None, limit=None, with_line_numbers=false))]
+    fn execute<'py>(
+        &self,
+        py: Python<'py>,
+        path: String,
+        offset: Option<i64>,
+        limit: Option<i64>,
+        with_line_numbers: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
         let this = self.clone();
         future_into_py(py, async move {
             let mut params = HashMap::new();
-            params.insert("path".to_string(), path);
-            Ok(this.execute_inner(&params).await)
+            params.insert("path".to_string(), TypedValue::String(path));
+            if let Some(n) = offset {
+                params.insert("offset".to_string(), TypedValue::Integer(n));
+            }
+            if let Some(n) = limit {
+                params.insert("limit".to_string(), TypedValue::Integer(n));
+            }
+            params.insert(
+                "with_line_numbers".to_string(),
+                TypedValue::Boolean(with_line_numbers),
+            );
+            Ok(this.execute_inner(&params, None).await)
         })
     }
 
@@ -121,9 +343,14 @@ impl ReadFileTool {
 // ============================================================================
 
 /// Tool to write content to a file.
+///
+/// When constructed with a `root`, every path is confined to that
+/// directory; paths that resolve outside it are rejected instead of written.
 #[pyclass]
 #[derive(Clone)]
-pub struct WriteFileTool;
+pub struct WriteFileTool {
+    root: Option<PathBuf>,
+}
 
 impl Tool for WriteFileTool {
     fn name(&self) -> &str {
@@ -138,8 +365,29 @@ impl Tool for WriteFileTool {
         let mut props = HashMap::new();
         props.insert("path".into(), string_prop("The file path to write to"));
         props.insert("content".into(), string_prop("The content to write"));
+        props.insert(
+            "overwrite".into(),
+            serde_json::json!({
+                "type": "boolean",
+                "description": "If false, fail instead of overwriting an existing file (default true)"
+            }),
+        );
+        props.insert(
+            "preserve_line_endings".into(),
+            serde_json::json!({
+                "type": "boolean",
+                "description": "If true (default), match the existing file's \\n vs \\r\\n line endings instead of whatever 'content' uses"
+            }),
+        );
         object_schema(props, vec!["path", "content"])
     }
+
+    fn conversions(&self) -> HashMap<String, Conversion> {
+        let mut map = HashMap::new();
+        map.insert("overwrite".to_string(), Conversion::Boolean);
+        map.insert("preserve_line_endings".to_string(), Conversion::Boolean);
+        map
+    }
 }
 
 impl WriteFileTool {
@@ -151,17 +399,41 @@ impl WriteFileTool {
         Tool::to_schema(self, py)
     }
 
-    pub async fn execute_inner(&self, params: &HashMap<String, String>) -> String {
-        let path = match params.get("path") {
+    pub fn conversions(&self) -> HashMap<String, Conversion> {
+        Tool::conversions(self)
+    }
+
+    pub async fn execute_inner(
+        &self,
+        params: &HashMap<String, TypedValue>,
+        on_chunk: Option<ChunkSender>,
+    ) -> String {
+        let path = match params.get("path").and_then(|v| v.as_str()) {
             Some(p) => p,
             None => return "Error: Missing required parameter 'path'".to_string(),
         };
-        let content = match params.get("content") {
+        let content = match params.get("content").and_then(|v| v.as_str()) {
             Some(c) => c,
             None => return "Error: Missing required parameter 'content'".to_string(),
         };
+        let overwrite = params
+            .get("overwrite")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let preserve_line_endings = params
+            .get("preserve_line_endings")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
 
         let file_path = expand_path(path);
+        let file_path = match resolve_within_root(self.root.as_deref(), &file_path).await {
+            Ok(p) => p,
+            Err(e) => return e,
+        };
+
+        if !overwrite && fs::metadata(&file_path).await.is_ok() {
+            return format!("Error: File already exists: {}", path);
+        }
 
         // Create parent directories if needed
         if let Some(parent) = file_path.parent() {
@@ -170,24 +442,31 @@ impl WriteFileTool {
             }
         }
 
-        match fs::write(&file_path, content).await {
-            Ok(()) => format!("Successfully wrote {} bytes to {}", content.len(), path),
-            Err(e) => {
-                if e.kind() == std::io::ErrorKind::PermissionDenied {
-                    format!("Error: Permission denied: {}", path)
-                } else {
-                    format!("Error writing file: {}", e)
-                }
+        let final_content = if preserve_line_endings {
+            match detect_existing_line_ending(&file_path).await {
+                Some(ending) => normalize_line_endings(content, ending),
+                None => content.to_string(),
             }
+        } else {
+            content.to_string()
+        };
+        let bytes = final_content.as_bytes();
+        let total = bytes.len();
+
+        if let Err(e) = atomic_write(&file_path, bytes, path, on_chunk.as_ref()).await {
+            return e;
         }
+
+        format!("Successfully wrote {} bytes to {}", total, path)
     }
 }
 
 #[pymethods]
 impl WriteFileTool {
     #[new]
-    fn new() -> Self {
-        Self
+    #[pyo3(signature = (root=None))]
+    fn new(root: Option<PathBuf>) -> Self {
+        Self { root }
     }
 
     #[getter]
@@ -209,18 +488,26 @@ impl WriteFileTool {
         Ok(result.into())
     }
 
+    #[pyo3(signature = (path, content, overwrite=true, preserve_line_endings=true))]
     fn execute<'py>(
         &self,
         py: Python<'py>,
         path: String,
         content: String,
+        overwrite: bool,
+        preserve_line_endings: bool,
     ) -> PyResult<Bound<'py, PyAny>> {
         let this = self.clone();
         future_into_py(py, async move {
             let mut params = HashMap::new();
-            params.insert("path".to_string(), path);
-            params.insert("content".to_string(), content);
-            Ok(this.execute_inner(&params).await)
+            params.insert("path".to_string(), TypedValue::String(path));
+            params.insert("content".to_string(), TypedValue::String(content));
+            params.insert("overwrite".to_string(), TypedValue::Boolean(overwrite));
+            params.insert(
+                "preserve_line_endings".to_string(),
+                TypedValue::Boolean(preserve_line_endings),
+            );
+            Ok(this.execute_inner(&params, None).await)
         })
     }
 
@@ -235,9 +522,14 @@ impl WriteFileTool {
 // ============================================================================
 
 /// Tool to edit a file by replacing text.
+///
+/// When constructed with a `root`, every path is confined to that
+/// directory; paths that resolve outside it are rejected instead of edited.
 #[pyclass]
 #[derive(Clone)]
-pub struct EditFileTool;
+pub struct EditFileTool {
+    root: Option<PathBuf>,
+}
 
 impl Tool for EditFileTool {
     fn name(&self) -> &str {
@@ -269,21 +561,33 @@ impl EditFileTool {
         Tool::to_schema(self, py)
     }
 
-    pub async fn execute_inner(&self, params: &HashMap<String, String>) -> String {
-        let path = match params.get("path") {
+    pub fn conversions(&self) -> HashMap<String, Conversion> {
+        Tool::conversions(self)
+    }
+
+    pub async fn execute_inner(
+        &self,
+        params: &HashMap<String, TypedValue>,
+        _on_chunk: Option<ChunkSender>,
+    ) -> String {
+        let path = match params.get("path").and_then(|v| v.as_str()) {
             Some(p) => p,
             None => return "Error: Missing required parameter 'path'".to_string(),
         };
-        let old_text = match params.get("old_text") {
+        let old_text = match params.get("old_text").and_then(|v| v.as_str()) {
             Some(t) => t,
             None => return "Error: Missing required parameter 'old_text'".to_string(),
         };
-        let new_text = match params.get("new_text") {
+        let new_text = match params.get("new_text").and_then(|v| v.as_str()) {
             Some(t) => t,
             None => return "Error: Missing required parameter 'new_text'".to_string(),
         };
 
         let file_path = expand_path(path);
+        let file_path = match resolve_within_root(self.root.as_deref(), &file_path).await {
+            Ok(p) => p,
+            Err(e) => return e,
+        };
 
         // Read current content
         let content = match fs::read_to_string(&file_path).await {
@@ -310,18 +614,13 @@ impl EditFileTool {
             );
         }
 
-        // Replace and write
+        // Replace and write atomically, via the same temp-file-then-rename
+        // path `write_file` uses, so a crash mid-edit can't truncate the file.
         let new_content = content.replacen(old_text, new_text, 1);
 
-        match fs::write(&file_path, new_content).await {
+        match atomic_write(&file_path, new_content.as_bytes(), path, None).await {
             Ok(()) => format!("Successfully edited {}", path),
-            Err(e) => {
-                if e.kind() == std::io::ErrorKind::PermissionDenied {
-                    format!("Error: Permission denied: {}", path)
-                } else {
-                    format!("Error writing file: {}", e)
-                }
-            }
+            Err(e) => e,
         }
     }
 }
@@ -329,8 +628,9 @@ impl EditFileTool {
 #[pymethods]
 impl EditFileTool {
     #[new]
-    fn new() -> Self {
-        Self
+    #[pyo3(signature = (root=None))]
+    fn new(root: Option<PathBuf>) -> Self {
+        Self { root }
     }
 
     #[getter]
@@ -362,10 +662,10 @@ impl EditFileTool {
         let this = self.clone();
         future_into_py(py, async move {
             let mut params = HashMap::new();
-            params.insert("path".to_string(), path);
-            params.insert("old_text".to_string(), old_text);
-            params.insert("new_text".to_string(), new_text);
-            Ok(this.execute_inner(&params).await)
+            params.insert("path".to_string(), TypedValue::String(path));
+            params.insert("old_text".to_string(), TypedValue::String(old_text));
+            params.insert("new_text".to_string(), TypedValue::String(new_text));
+            Ok(this.execute_inner(&params, None).await)
         })
     }
 
@@ -376,111 +676,239 @@ impl EditFileTool {
 }
 
 // ============================================================================
-// ListDirTool
+// SearchFileTool
 // ============================================================================
 
-/// Tool to list directory contents.
+/// Single-`*`-wildcard glob match against a file name, e.g. `*.rs`.
+///
+/// Duplicated from the equivalent matcher in `context.rs` rather than
+/// shared, matching this crate's existing convention of small per-file
+/// utilities over a shared `mod utils`.
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, suffix)) => {
+            name.len() >= prefix.len() + suffix.len()
+                && name.starts_with(prefix)
+                && name.ends_with(suffix)
+        }
+    }
+}
+
+/// A single matched line, ready to be rendered inline as `path:line: text`.
+struct SearchMatch {
+    path: String,
+    line_number: usize,
+    text: String,
+}
+
+/// Tool to recursively search a directory tree for a pattern.
+///
+/// When constructed with a `root`, every path is confined to that
+/// directory; paths that resolve outside it are rejected instead of searched.
 #[pyclass]
 #[derive(Clone)]
-pub struct ListDirTool;
+pub struct SearchFileTool {
+    root: Option<PathBuf>,
+}
 
-impl Tool for ListDirTool {
+impl Tool for SearchFileTool {
     fn name(&self) -> &str {
-        "list_dir"
+        "search_files"
     }
 
     fn description(&self) -> &str {
-        "List the contents of a directory."
+        "Recursively search a directory tree for a literal or regex pattern, reporting each matching line inline with its file path and line number."
     }
 
     fn parameters(&self) -> HashMap<String, serde_json::Value> {
         let mut props = HashMap::new();
-        props.insert("path".into(), string_prop("The directory path to list"));
-        object_schema(props, vec!["path"])
+        props.insert("path".into(), string_prop("The directory to search recursively"));
+        props.insert("pattern".into(), string_prop("The literal text or regex pattern to search for"));
+        props.insert(
+            "is_regex".into(),
+            serde_json::json!({
+                "type": "boolean",
+                "description": "Treat 'pattern' as a regex instead of literal text (default false)"
+            }),
+        );
+        props.insert(
+            "max_results".into(),
+            int_prop("Maximum number of matches to return"),
+        );
+        props.insert(
+            "glob".into(),
+            string_prop("Only search files whose name matches this single-wildcard glob, e.g. '*.rs'"),
+        );
+        object_schema(props, vec!["path", "pattern"])
+    }
+
+    fn conversions(&self) -> HashMap<String, Conversion> {
+        let mut map = HashMap::new();
+        map.insert("is_regex".to_string(), Conversion::Boolean);
+        map.insert("max_results".to_string(), Conversion::Integer);
+        map
     }
 }
 
-impl ListDirTool {
+impl SearchFileTool {
     pub fn tool_name(&self) -> &str {
-        "list_dir"
+        "search_files"
     }
 
     pub fn to_schema(&self, py: Python<'_>) -> PyResult<ToolSchema> {
         Tool::to_schema(self, py)
     }
 
-    pub async fn execute_inner(&self, params: &HashMap<String, String>) -> String {
-        let path = match params.get("path") {
+    pub fn conversions(&self) -> HashMap<String, Conversion> {
+        Tool::conversions(self)
+    }
+
+    pub async fn execute_inner(
+        &self,
+        params: &HashMap<String, TypedValue>,
+        _on_chunk: Option<ChunkSender>,
+    ) -> String {
+        let path = match params.get("path").and_then(|v| v.as_str()) {
             Some(p) => p,
             None => return "Error: Missing required parameter 'path'".to_string(),
         };
-
-        let dir_path = expand_path(path);
-
-        // Check if path exists and is a directory
-        let metadata = match fs::metadata(&dir_path).await {
-            Ok(m) => m,
-            Err(e) => {
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    return format!("Error: Directory not found: {}", path);
-                }
-                return format!("Error: {}", e);
+        let pattern = match params.get("pattern").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return "Error: Missing required parameter 'pattern'".to_string(),
+        };
+        let is_regex = params
+            .get("is_regex")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let max_results = params
+            .get("max_results")
+            .and_then(|v| v.as_i64())
+            .map(|n| n.max(0) as usize);
+        let glob = params.get("glob").and_then(|v| v.as_str());
+
+        let regex = if is_regex {
+            match regex::Regex::new(pattern) {
+                Ok(re) => Some(re),
+                Err(e) => return format!("Error: invalid regex pattern: {}", e),
             }
+        } else {
+            None
         };
 
-        if !metadata.is_dir() {
-            return format!("Error: Not a directory: {}", path);
+        let root = expand_path(path);
+        let root = match resolve_within_root(self.root.as_deref(), &root).await {
+            Ok(p) => p,
+            Err(e) => return e,
+        };
+        match fs::metadata(&root).await {
+            Ok(m) if m.is_dir() => {}
+            Ok(_) => return format!("Error: Not a directory: {}", path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return format!("Error: Directory not found: {}", path);
+            }
+            Err(e) => return format!("Error: {}", e),
         }
 
-        // Read directory entries
-        let mut entries = match fs::read_dir(&dir_path).await {
-            Ok(e) => e,
-            Err(e) => {
-                if e.kind() == std::io::ErrorKind::PermissionDenied {
-                    return format!("Error: Permission denied: {}", path);
+        let mut matches: Vec<SearchMatch> = Vec::new();
+        let mut dirs = vec![root.clone()];
+        let mut truncated = false;
+
+        'walk: while let Some(dir) = dirs.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let entry_path = entry.path();
+                let is_dir = entry.file_type().await.map(|t| t.is_dir()).unwrap_or(false);
+
+                if is_dir {
+                    dirs.push(entry_path);
+                    continue;
                 }
-                return format!("Error listing directory: {}", e);
-            }
-        };
 
-        let mut items: Vec<(String, bool)> = Vec::new();
+                if let Some(pattern) = glob {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    if !matches_glob(pattern, &name) {
+                        continue;
+                    }
+                }
 
-        while let Ok(Some(entry)) = entries.next_entry().await {
-            let name = entry.file_name().to_string_lossy().to_string();
-            let is_dir = entry.file_type().await.map(|t| t.is_dir()).unwrap_or(false);
-            items.push((name, is_dir));
+                let content = match fs::read_to_string(&entry_path).await {
+                    Ok(c) => c,
+                    // Not UTF-8 (likely a binary file) or unreadable - skip it.
+                    Err(_) => continue,
+                };
+
+                let display_path = display_relative(&entry_path, &root, path);
+
+                for (idx, line) in content.lines().enumerate() {
+                    let is_match = match &regex {
+                        Some(re) => re.is_match(line),
+                        None => line.contains(pattern),
+                    };
+                    if !is_match {
+                        continue;
+                    }
+
+                    matches.push(SearchMatch {
+                        path: display_path.clone(),
+                        line_number: idx + 1,
+                        text: line.to_string(),
+                    });
+
+                    if let Some(max) = max_results {
+                        if matches.len() >= max {
+                            truncated = true;
+                            break 'walk;
+                        }
+                    }
+                }
+            }
         }
 
-        if items.is_empty() {
-            return format!("Directory {} is empty", path);
+        if matches.is_empty() {
+            return format!("No matches found for '{}' in {}", pattern, path);
         }
 
-        // Sort items
-        items.sort_by(|a, b| a.0.cmp(&b.0));
-
-        // Format output
-        let output: Vec<String> = items
-            .into_iter()
-            .map(|(name, is_dir)| {
-                let prefix = if is_dir { "\u{1F4C1} " } else { "\u{1F4C4} " };
-                format!("{}{}", prefix, name)
-            })
+        let mut output: Vec<String> = matches
+            .iter()
+            .map(|m| format!("{}:{}: {}", m.path, m.line_number, m.text))
             .collect();
 
+        if truncated {
+            output.push(format!(
+                "... (stopped after {} matches, more may exist)",
+                matches.len()
+            ));
+        }
+
         output.join("\n")
     }
 }
 
+/// Render `entry_path` relative to the searched root, prefixed with the
+/// caller's original (possibly `~`-relative) `path` argument.
+fn display_relative(entry_path: &Path, root: &Path, path: &str) -> String {
+    match entry_path.strip_prefix(root) {
+        Ok(rel) => format!("{}/{}", path.trim_end_matches('/'), rel.display()),
+        Err(_) => entry_path.display().to_string(),
+    }
+}
+
 #[pymethods]
-impl ListDirTool {
+impl SearchFileTool {
     #[new]
-    fn new() -> Self {
-        Self
+    #[pyo3(signature = (root=None))]
+    fn new(root: Option<PathBuf>) -> Self {
+        Self { root }
     }
 
     #[getter]
     fn name(&self) -> &str {
-        "list_dir"
+        "search_files"
     }
 
     #[getter]
@@ -497,12 +925,1044 @@ impl ListDirTool {
         Ok(result.into())
     }
 
-    fn execute<'py>(&self, py: Python<'py>, path: String) -> PyResult<Bound<'py, PyAny>> {
+    #[pyo3(signature = (path, pattern, is_regex=false, max_results=None, glob=None))]
+    fn execute<'py>(
+        &self,
+        py: Python<'py>,
+        path: String,
+        pattern: String,
+        is_regex: bool,
+        max_results: Option<i64>,
+        glob: Option<String>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let this = self.clone();
+        future_into_py(py, async move {
+            let mut params = HashMap::new();
+            params.insert("path".to_string(), TypedValue::String(path));
+            params.insert("pattern".to_string(), TypedValue::String(pattern));
+            params.insert("is_regex".to_string(), TypedValue::Boolean(is_regex));
+            if let Some(n) = max_results {
+                params.insert("max_results".to_string(), TypedValue::Integer(n));
+            }
+            if let Some(g) = glob {
+                params.insert("glob".to_string(), TypedValue::String(g));
+            }
+            Ok(this.execute_inner(&params, None).await)
+        })
+    }
+
+    fn to_schema_py(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let schema = Tool::to_schema(self, py)?;
+        schema.to_dict(py)
+    }
+}
+
+// ============================================================================
+// Gitignore-aware recursive tree walk (ListDirTool's `recursive` mode)
+// ============================================================================
+
+/// A single parsed `.gitignore` line, plus the directory it came from -
+/// rules are matched against paths relative to that directory, not the
+/// listing root, so a rule from a subdirectory's `.gitignore` only ever
+/// applies within that subtree.
+#[derive(Clone)]
+struct IgnoreRule {
+    base: PathBuf,
+    pattern: String,
+    negate: bool,
+    anchored: bool,
+    dir_only: bool,
+}
+
+/// Parse a `.gitignore` file's contents into rules rooted at `base` (the
+/// directory the file lives in).
+fn parse_gitignore(base: &Path, content: &str) -> Vec<IgnoreRule> {
+    let mut rules = Vec::new();
+    for raw_line in content.lines() {
+        let line = raw_line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let negate = line.starts_with('!');
+        let mut pattern = if negate { &line[1..] } else { line };
+        let dir_only = pattern.len() > 1 && pattern.ends_with('/');
+        if dir_only {
+            pattern = &pattern[..pattern.len() - 1];
+        }
+        let anchored = pattern.starts_with('/') || pattern.contains('/');
+        let pattern = pattern.trim_start_matches('/').to_string();
+        if pattern.is_empty() {
+            continue;
+        }
+        rules.push(IgnoreRule {
+            base: base.to_path_buf(),
+            pattern,
+            negate,
+            anchored,
+            dir_only,
+        });
+    }
+    rules
+}
+
+/// Glob match supporting `*`, `?`, and `**` (matching across path
+/// separators), as used by `.gitignore` patterns.
+fn glob_match_path(pattern: &[u8], text: &[u8]) -> bool {
+    match (pattern.first(), text.first()) {
+        (None, None) => true,
+        (None, Some(_)) => false,
+        (Some(b'*'), _) if pattern.get(1) == Some(&b'*') => {
+            let mut rest = &pattern[2..];
+            if rest.first() == Some(&b'/') {
+                rest = &rest[1..];
+            }
+            if glob_match_path(rest, text) {
+                return true;
+            }
+            if text.is_empty() {
+                return false;
+            }
+            glob_match_path(pattern, &text[1..])
+        }
+        (Some(b'*'), _) => {
+            if glob_match_path(&pattern[1..], text) {
+                return true;
+            }
+            match text.first() {
+                Some(&c) if c != b'/' => glob_match_path(pattern, &text[1..]),
+                _ => false,
+            }
+        }
+        (Some(b'?'), Some(&c)) if c != b'/' => glob_match_path(&pattern[1..], &text[1..]),
+        (Some(&pc), Some(&tc)) if pc == tc => glob_match_path(&pattern[1..], &text[1..]),
+        _ => false,
+    }
+}
+
+/// Whether `entry_path` is ignored by the accumulated `rules` (outermost
+/// directory's rules first, innermost last) - the last matching rule wins,
+/// so a later `!pattern` can un-ignore something an earlier rule excluded.
+fn is_ignored(rules: &[IgnoreRule], entry_path: &Path, is_dir: bool) -> bool {
+    let mut ignored = false;
+    for rule in rules {
+        if rule.dir_only && !is_dir {
+            continue;
+        }
+        let Ok(rel) = entry_path.strip_prefix(&rule.base) else {
+            continue;
+        };
+        let rel = rel.to_string_lossy().replace('\\', "/");
+
+        let matched = if rule.anchored {
+            glob_match_path(rule.pattern.as_bytes(), rel.as_bytes())
+        } else {
+            rel.rsplit('/')
+                .next()
+                .map(|name| glob_match_path(rule.pattern.as_bytes(), name.as_bytes()))
+                .unwrap_or(false)
+        };
+
+        if matched {
+            ignored = !rule.negate;
+        }
+    }
+    ignored
+}
+
+/// Whether `dir` has at least one entry, used to decide whether a
+/// depth-truncated directory needs an `...` marker.
+async fn directory_has_entries(dir: &Path) -> bool {
+    match fs::read_dir(dir).await {
+        Ok(mut entries) => matches!(entries.next_entry().await, Ok(Some(_))),
+        Err(_) => false,
+    }
+}
+
+/// Recursively render `dir`'s contents into `lines` as an indented tree,
+/// honoring accumulated `.gitignore` rules and `max_depth` (the number of
+/// listed levels below the root; `None` means unlimited). `.git` is always
+/// pruned regardless of `respect_gitignore`.
+fn build_tree<'a>(
+    dir: &'a Path,
+    depth: usize,
+    max_depth: Option<usize>,
+    respect_gitignore: bool,
+    mut rules: Vec<IgnoreRule>,
+    lines: &'a mut Vec<String>,
+) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if respect_gitignore {
+            if let Ok(content) = fs::read_to_string(dir.join(".gitignore")).await {
+                rules.extend(parse_gitignore(dir, &content));
+            }
+        }
+
+        let mut entries = match fs::read_dir(dir).await {
+            Ok(e) => e,
+            Err(_) => return,
+        };
+
+        let mut items: Vec<(PathBuf, String, bool)> = Vec::new();
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == ".git" {
+                continue;
+            }
+            let is_dir = entry.file_type().await.map(|t| t.is_dir()).unwrap_or(false);
+            let entry_path = entry.path();
+            if respect_gitignore && is_ignored(&rules, &entry_path, is_dir) {
+                continue;
+            }
+            items.push((entry_path, name, is_dir));
+        }
+        items.sort_by(|a, b| a.1.cmp(&b.1));
+
+        for (entry_path, name, is_dir) in items {
+            let indent = "  ".repeat(depth);
+            let prefix = if is_dir { "\u{1F4C1} " } else { "\u{1F4C4} " };
+            lines.push(format!("{}{}{}", indent, prefix, name));
+
+            if !is_dir {
+                continue;
+            }
+
+            if max_depth.map(|m| depth + 1 >= m).unwrap_or(false) {
+                if directory_has_entries(&entry_path).await {
+                    lines.push(format!("{}  ...", indent));
+                }
+                continue;
+            }
+
+            build_tree(
+                &entry_path,
+                depth + 1,
+                max_depth,
+                respect_gitignore,
+                rules.clone(),
+                lines,
+            )
+            .await;
+        }
+    })
+}
+
+// ============================================================================
+// ListDirTool
+// ============================================================================
+
+/// Tool to list directory contents.
+///
+/// When constructed with a `root`, every path is confined to that
+/// directory; paths that resolve outside it are rejected instead of listed.
+#[pyclass]
+#[derive(Clone)]
+pub struct ListDirTool {
+    root: Option<PathBuf>,
+}
+
+impl Tool for ListDirTool {
+    fn name(&self) -> &str {
+        "list_dir"
+    }
+
+    fn description(&self) -> &str {
+        "List the contents of a directory. Optionally recurse into an ignore-aware indented tree."
+    }
+
+    fn parameters(&self) -> HashMap<String, serde_json::Value> {
+        let mut props = HashMap::new();
+        props.insert("path".into(), string_prop("The directory path to list"));
+        props.insert(
+            "recursive".into(),
+            serde_json::json!({
+                "type": "boolean",
+                "description": "List the full subtree as an indented tree instead of just the top level (default false)"
+            }),
+        );
+        props.insert(
+            "max_depth".into(),
+            int_prop("Maximum number of levels to list when 'recursive' is true, counting the top level as 1; deeper directories are shown with '...' (unlimited if omitted)"),
+        );
+        props.insert(
+            "respect_gitignore".into(),
+            serde_json::json!({
+                "type": "boolean",
+                "description": "When 'recursive' is true, skip paths matched by .gitignore files encountered along the way (default true); .git is always skipped"
+            }),
+        );
+        object_schema(props, vec!["path"])
+    }
+
+    fn conversions(&self) -> HashMap<String, Conversion> {
+        let mut map = HashMap::new();
+        map.insert("recursive".to_string(), Conversion::Boolean);
+        map.insert("max_depth".to_string(), Conversion::Integer);
+        map.insert("respect_gitignore".to_string(), Conversion::Boolean);
+        map
+    }
+}
+
+impl ListDirTool {
+    pub fn tool_name(&self) -> &str {
+        "list_dir"
+    }
+
+    pub fn to_schema(&self, py: Python<'_>) -> PyResult<ToolSchema> {
+        Tool::to_schema(self, py)
+    }
+
+    pub fn conversions(&self) -> HashMap<String, Conversion> {
+        Tool::conversions(self)
+    }
+
+    pub async fn execute_inner(
+        &self,
+        params: &HashMap<String, TypedValue>,
+        _on_chunk: Option<ChunkSender>,
+    ) -> String {
+        let path = match params.get("path").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return "Error: Missing required parameter 'path'".to_string(),
+        };
+
+        let dir_path = expand_path(path);
+        let dir_path = match resolve_within_root(self.root.as_deref(), &dir_path).await {
+            Ok(p) => p,
+            Err(e) => return e,
+        };
+
+        // Check if path exists and is a directory
+        let metadata = match fs::metadata(&dir_path).await {
+            Ok(m) => m,
+            Err(e) => {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    return format!("Error: Directory not found: {}", path);
+                }
+                return format!("Error: {}", e);
+            }
+        };
+
+        if !metadata.is_dir() {
+            return format!("Error: Not a directory: {}", path);
+        }
+
+        let recursive = params
+            .get("recursive")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        if recursive {
+            let max_depth = params
+                .get("max_depth")
+                .and_then(|v| v.as_i64())
+                .map(|n| n.max(0) as usize);
+            let respect_gitignore = params
+                .get("respect_gitignore")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true);
+
+            let mut lines = Vec::new();
+            build_tree(&dir_path, 0, max_depth, respect_gitignore, Vec::new(), &mut lines).await;
+
+            return if lines.is_empty() {
+                format!("Directory {} is empty", path)
+            } else {
+                lines.join("\n")
+            };
+        }
+
+        // Read directory entries
+        let mut entries = match fs::read_dir(&dir_path).await {
+            Ok(e) => e,
+            Err(e) => {
+                if e.kind() == std::io::ErrorKind::PermissionDenied {
+                    return format!("Error: Permission denied: {}", path);
+                }
+                return format!("Error listing directory: {}", e);
+            }
+        };
+
+        let mut items: Vec<(String, bool)> = Vec::new();
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let is_dir = entry.file_type().await.map(|t| t.is_dir()).unwrap_or(false);
+            items.push((name, is_dir));
+        }
+
+        if items.is_empty() {
+            return format!("Directory {} is empty", path);
+        }
+
+        // Sort items
+        items.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // Format output
+        let output: Vec<String> = items
+            .into_iter()
+            .map(|(name, is_dir)| {
+                let prefix = if is_dir { "\u{1F4C1} " } else { "\u{1F4C4} " };
+                format!("{}{}", prefix, name)
+            })
+            .collect();
+
+        output.join("\n")
+    }
+}
+
+#[pymethods]
+impl ListDirTool {
+    #[new]
+    #[pyo3(signature = (root=None))]
+    fn new(root: Option<PathBuf>) -> Self {
+        Self { root }
+    }
+
+    #[getter]
+    fn name(&self) -> &str {
+        "list_dir"
+    }
+
+    #[getter]
+    fn description(&self) -> &str {
+        Tool::description(self)
+    }
+
+    #[getter]
+    fn parameters(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let params = Tool::parameters(self);
+        let json_str = serde_json::to_string(&params)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let result = py.import("json")?.call_method1("loads", (json_str,))?;
+        Ok(result.into())
+    }
+
+    #[pyo3(signature = (path, recursive=false, max_depth=None, respect_gitignore=true))]
+    fn execute<'py>(
+        &self,
+        py: Python<'py>,
+        path: String,
+        recursive: bool,
+        max_depth: Option<i64>,
+        respect_gitignore: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let this = self.clone();
+        future_into_py(py, async move {
+            let mut params = HashMap::new();
+            params.insert("path".to_string(), TypedValue::String(path));
+            params.insert("recursive".to_string(), TypedValue::Boolean(recursive));
+            if let Some(n) = max_depth {
+                params.insert("max_depth".to_string(), TypedValue::Integer(n));
+            }
+            params.insert(
+                "respect_gitignore".to_string(),
+                TypedValue::Boolean(respect_gitignore),
+            );
+            Ok(this.execute_inner(&params, None).await)
+        })
+    }
+
+    fn to_schema_py(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let schema = Tool::to_schema(self, py)?;
+        schema.to_dict(py)
+    }
+}
+
+/// Recursively copy `src`'s contents into `dst`, creating directories as
+/// needed. Used by `CopyFileTool` directly and by `MoveFileTool` as its
+/// cross-filesystem fallback.
+fn copy_dir_recursive<'a>(
+    src: &'a Path,
+    dst: &'a Path,
+) -> std::pin::Pin<Box<dyn std::future::Future<Output = std::io::Result<()>> + Send + 'a>> {
+    Box::pin(async move {
+        fs::create_dir_all(dst).await?;
+        let mut entries = fs::read_dir(src).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let from = entry.path();
+            let to = dst.join(entry.file_name());
+            if entry.file_type().await?.is_dir() {
+                copy_dir_recursive(&from, &to).await?;
+            } else {
+                fs::copy(&from, &to).await?;
+            }
+        }
+        Ok(())
+    })
+}
+
+// ============================================================================
+// CopyFileTool
+// ============================================================================
+
+/// Tool to copy a file, or a directory tree when `recursive` is true.
+///
+/// When constructed with a `root`, both `src` and `dst` are confined to
+/// that directory; paths that resolve outside it are rejected.
+#[pyclass]
+#[derive(Clone)]
+pub struct CopyFileTool {
+    root: Option<PathBuf>,
+}
+
+impl Tool for CopyFileTool {
+    fn name(&self) -> &str {
+        "copy_file"
+    }
+
+    fn description(&self) -> &str {
+        "Copy a file, or a directory tree when 'recursive' is true, to a new path."
+    }
+
+    fn parameters(&self) -> HashMap<String, serde_json::Value> {
+        let mut props = HashMap::new();
+        props.insert("src".into(), string_prop("The path to copy from"));
+        props.insert("dst".into(), string_prop("The path to copy to"));
+        props.insert(
+            "recursive".into(),
+            serde_json::json!({
+                "type": "boolean",
+                "description": "Required to copy a directory and its contents (default false)"
+            }),
+        );
+        props.insert(
+            "overwrite".into(),
+            serde_json::json!({
+                "type": "boolean",
+                "description": "If false, fail instead of overwriting an existing destination (default true)"
+            }),
+        );
+        object_schema(props, vec!["src", "dst"])
+    }
+
+    fn conversions(&self) -> HashMap<String, Conversion> {
+        let mut map = HashMap::new();
+        map.insert("recursive".to_string(), Conversion::Boolean);
+        map.insert("overwrite".to_string(), Conversion::Boolean);
+        map
+    }
+}
+
+impl CopyFileTool {
+    pub fn tool_name(&self) -> &str {
+        "copy_file"
+    }
+
+    pub fn to_schema(&self, py: Python<'_>) -> PyResult<ToolSchema> {
+        Tool::to_schema(self, py)
+    }
+
+    pub fn conversions(&self) -> HashMap<String, Conversion> {
+        Tool::conversions(self)
+    }
+
+    pub async fn execute_inner(
+        &self,
+        params: &HashMap<String, TypedValue>,
+        _on_chunk: Option<ChunkSender>,
+    ) -> String {
+        let src = match params.get("src").and_then(|v| v.as_str()) {
+            Some(s) => s,
+            None => return "Error: Missing required parameter 'src'".to_string(),
+        };
+        let dst = match params.get("dst").and_then(|v| v.as_str()) {
+            Some(d) => d,
+            None => return "Error: Missing required parameter 'dst'".to_string(),
+        };
+        let recursive = params
+            .get("recursive")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let overwrite = params
+            .get("overwrite")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
+        let src_path = expand_path(src);
+        let src_path = match resolve_within_root(self.root.as_deref(), &src_path).await {
+            Ok(p) => p,
+            Err(e) => return e,
+        };
+        let dst_path = expand_path(dst);
+        let dst_path = match resolve_within_root(self.root.as_deref(), &dst_path).await {
+            Ok(p) => p,
+            Err(e) => return e,
+        };
+
+        let metadata = match fs::metadata(&src_path).await {
+            Ok(m) => m,
+            Err(e) => {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    return format!("Error: Path not found: {}", src);
+                }
+                return format!("Error: {}", e);
+            }
+        };
+
+        if !overwrite && fs::metadata(&dst_path).await.is_ok() {
+            return format!("Error: Destination already exists: {}", dst);
+        }
+
+        if metadata.is_dir() {
+            if !recursive {
+                return format!(
+                    "Error: '{}' is a directory; pass recursive=true to copy it",
+                    src
+                );
+            }
+            if let Err(e) = copy_dir_recursive(&src_path, &dst_path).await {
+                return format!("Error copying directory: {}", e);
+            }
+        } else {
+            if let Some(parent) = dst_path.parent() {
+                if let Err(e) = fs::create_dir_all(parent).await {
+                    return format!("Error creating directories: {}", e);
+                }
+            }
+            if let Err(e) = fs::copy(&src_path, &dst_path).await {
+                return if e.kind() == std::io::ErrorKind::PermissionDenied {
+                    format!("Error: Permission denied: {}", dst)
+                } else {
+                    format!("Error copying file: {}", e)
+                };
+            }
+        }
+
+        format!("Successfully copied {} to {}", src, dst)
+    }
+}
+
+#[pymethods]
+impl CopyFileTool {
+    #[new]
+    #[pyo3(signature = (root=None))]
+    fn new(root: Option<PathBuf>) -> Self {
+        Self { root }
+    }
+
+    #[getter]
+    fn name(&self) -> &str {
+        "copy_file"
+    }
+
+    #[getter]
+    fn description(&self) -> &str {
+        Tool::description(self)
+    }
+
+    #[getter]
+    fn parameters(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let params = Tool::parameters(self);
+        let json_str = serde_json::to_string(&params)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let result = py.import("json")?.call_method1("loads", (json_str,))?;
+        Ok(result.into())
+    }
+
+    #[pyo3(signature = (src, dst, recursive=false, overwrite=true))]
+    fn execute<'py>(
+        &self,
+        py: Python<'py>,
+        src: String,
+        dst: String,
+        recursive: bool,
+        overwrite: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let this = self.clone();
+        future_into_py(py, async move {
+            let mut params = HashMap::new();
+            params.insert("src".to_string(), TypedValue::String(src));
+            params.insert("dst".to_string(), TypedValue::String(dst));
+            params.insert("recursive".to_string(), TypedValue::Boolean(recursive));
+            params.insert("overwrite".to_string(), TypedValue::Boolean(overwrite));
+            Ok(this.execute_inner(&params, None).await)
+        })
+    }
+
+    fn to_schema_py(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let schema = Tool::to_schema(self, py)?;
+        schema.to_dict(py)
+    }
+}
+
+// ============================================================================
+// MoveFileTool
+// ============================================================================
+
+/// Tool to move (rename) a file, or a directory tree when `recursive` is
+/// true, to a new path.
+///
+/// When constructed with a `root`, both `src` and `dst` are confined to
+/// that directory; paths that resolve outside it are rejected. A move
+/// within the same filesystem is a fast atomic rename; a move across
+/// filesystems falls back to copying the source then deleting it.
+#[pyclass]
+#[derive(Clone)]
+pub struct MoveFileTool {
+    root: Option<PathBuf>,
+}
+
+impl Tool for MoveFileTool {
+    fn name(&self) -> &str {
+        "move_file"
+    }
+
+    fn description(&self) -> &str {
+        "Move (rename) a file, or a directory tree when 'recursive' is true, to a new path."
+    }
+
+    fn parameters(&self) -> HashMap<String, serde_json::Value> {
+        let mut props = HashMap::new();
+        props.insert("src".into(), string_prop("The path to move from"));
+        props.insert("dst".into(), string_prop("The path to move to"));
+        props.insert(
+            "recursive".into(),
+            serde_json::json!({
+                "type": "boolean",
+                "description": "Required to move a directory and its contents (default false)"
+            }),
+        );
+        props.insert(
+            "overwrite".into(),
+            serde_json::json!({
+                "type": "boolean",
+                "description": "If false, fail instead of overwriting an existing destination (default true)"
+            }),
+        );
+        object_schema(props, vec!["src", "dst"])
+    }
+
+    fn conversions(&self) -> HashMap<String, Conversion> {
+        let mut map = HashMap::new();
+        map.insert("recursive".to_string(), Conversion::Boolean);
+        map.insert("overwrite".to_string(), Conversion::Boolean);
+        map
+    }
+}
+
+impl MoveFileTool {
+    pub fn tool_name(&self) -> &str {
+        "move_file"
+    }
+
+    pub fn to_schema(&self, py: Python<'_>) -> PyResult<ToolSchema> {
+        Tool::to_schema(self, py)
+    }
+
+    pub fn conversions(&self) -> HashMap<String, Conversion> {
+        Tool::conversions(self)
+    }
+
+    pub async fn execute_inner(
+        &self,
+        params: &HashMap<String, TypedValue>,
+        _on_chunk: Option<ChunkSender>,
+    ) -> String {
+        let src = match params.get("src").and_then(|v| v.as_str()) {
+            Some(s) => s,
+            None => return "Error: Missing required parameter 'src'".to_string(),
+        };
+        let dst = match params.get("dst").and_then(|v| v.as_str()) {
+            Some(d) => d,
+            None => return "Error: Missing required parameter 'dst'".to_string(),
+        };
+        let recursive = params
+            .get("recursive")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let overwrite = params
+            .get("overwrite")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
+        let src_path = expand_path(src);
+        let src_path = match resolve_within_root(self.root.as_deref(), &src_path).await {
+            Ok(p) => p,
+            Err(e) => return e,
+        };
+        let dst_path = expand_path(dst);
+        let dst_path = match resolve_within_root(self.root.as_deref(), &dst_path).await {
+            Ok(p) => p,
+            Err(e) => return e,
+        };
+
+        let metadata = match fs::metadata(&src_path).await {
+            Ok(m) => m,
+            Err(e) => {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    return format!("Error: Path not found: {}", src);
+                }
+                return format!("Error: {}", e);
+            }
+        };
+
+        if metadata.is_dir() && !recursive {
+            return format!(
+                "Error: '{}' is a directory; pass recursive=true to move it",
+                src
+            );
+        }
+
+        let dst_exists_meta = fs::metadata(&dst_path).await.ok();
+        if dst_exists_meta.is_some() && !overwrite {
+            return format!("Error: Destination already exists: {}", dst);
+        }
+        if let Some(existing) = &dst_exists_meta {
+            let remove_result = if existing.is_dir() {
+                fs::remove_dir_all(&dst_path).await
+            } else {
+                fs::remove_file(&dst_path).await
+            };
+            if let Err(e) = remove_result {
+                return format!("Error removing existing destination: {}", e);
+            }
+        }
+
+        if let Some(parent) = dst_path.parent() {
+            if let Err(e) = fs::create_dir_all(parent).await {
+                return format!("Error creating directories: {}", e);
+            }
+        }
+
+        if fs::rename(&src_path, &dst_path).await.is_err() {
+            // Same-filesystem rename failed (most likely EXDEV, a move
+            // across filesystems) - fall back to copy then delete.
+            let copy_result = if metadata.is_dir() {
+                copy_dir_recursive(&src_path, &dst_path).await
+            } else {
+                fs::copy(&src_path, &dst_path).await.map(|_| ())
+            };
+            if let Err(e) = copy_result {
+                return format!("Error moving {}: {}", src, e);
+            }
+            let remove_result = if metadata.is_dir() {
+                fs::remove_dir_all(&src_path).await
+            } else {
+                fs::remove_file(&src_path).await
+            };
+            if let Err(e) = remove_result {
+                return format!(
+                    "Copied {} to {} but failed to remove the source: {}",
+                    src, dst, e
+                );
+            }
+        }
+
+        format!("Successfully moved {} to {}", src, dst)
+    }
+}
+
+#[pymethods]
+impl MoveFileTool {
+    #[new]
+    #[pyo3(signature = (root=None))]
+    fn new(root: Option<PathBuf>) -> Self {
+        Self { root }
+    }
+
+    #[getter]
+    fn name(&self) -> &str {
+        "move_file"
+    }
+
+    #[getter]
+    fn description(&self) -> &str {
+        Tool::description(self)
+    }
+
+    #[getter]
+    fn parameters(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let params = Tool::parameters(self);
+        let json_str = serde_json::to_string(&params)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let result = py.import("json")?.call_method1("loads", (json_str,))?;
+        Ok(result.into())
+    }
+
+    #[pyo3(signature = (src, dst, recursive=false, overwrite=true))]
+    fn execute<'py>(
+        &self,
+        py: Python<'py>,
+        src: String,
+        dst: String,
+        recursive: bool,
+        overwrite: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let this = self.clone();
+        future_into_py(py, async move {
+            let mut params = HashMap::new();
+            params.insert("src".to_string(), TypedValue::String(src));
+            params.insert("dst".to_string(), TypedValue::String(dst));
+            params.insert("recursive".to_string(), TypedValue::Boolean(recursive));
+            params.insert("overwrite".to_string(), TypedValue::Boolean(overwrite));
+            Ok(this.execute_inner(&params, None).await)
+        })
+    }
+
+    fn to_schema_py(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let schema = Tool::to_schema(self, py)?;
+        schema.to_dict(py)
+    }
+}
+
+// ============================================================================
+// DeleteFileTool
+// ============================================================================
+
+/// Tool to delete a file, or a directory tree when `recursive` is true.
+///
+/// When constructed with a `root`, `path` is confined to that directory;
+/// paths that resolve outside it are rejected. Deleting a non-empty
+/// directory requires `recursive=true`, matching the caution `rm` asks for.
+#[pyclass]
+#[derive(Clone)]
+pub struct DeleteFileTool {
+    root: Option<PathBuf>,
+}
+
+impl Tool for DeleteFileTool {
+    fn name(&self) -> &str {
+        "delete_file"
+    }
+
+    fn description(&self) -> &str {
+        "Delete a file, or a directory tree when 'recursive' is true."
+    }
+
+    fn parameters(&self) -> HashMap<String, serde_json::Value> {
+        let mut props = HashMap::new();
+        props.insert("path".into(), string_prop("The path to delete"));
+        props.insert(
+            "recursive".into(),
+            serde_json::json!({
+                "type": "boolean",
+                "description": "Required to delete a non-empty directory and its contents (default false)"
+            }),
+        );
+        object_schema(props, vec!["path"])
+    }
+
+    fn conversions(&self) -> HashMap<String, Conversion> {
+        let mut map = HashMap::new();
+        map.insert("recursive".to_string(), Conversion::Boolean);
+        map
+    }
+}
+
+impl DeleteFileTool {
+    pub fn tool_name(&self) -> &str {
+        "delete_file"
+    }
+
+    pub fn to_schema(&self, py: Python<'_>) -> PyResult<ToolSchema> {
+        Tool::to_schema(self, py)
+    }
+
+    pub fn conversions(&self) -> HashMap<String, Conversion> {
+        Tool::conversions(self)
+    }
+
+    pub async fn execute_inner(
+        &self,
+        params: &HashMap<String, TypedValue>,
+        _on_chunk: Option<ChunkSender>,
+    ) -> String {
+        let path = match params.get("path").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return "Error: Missing required parameter 'path'".to_string(),
+        };
+        let recursive = params
+            .get("recursive")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let file_path = expand_path(path);
+        let file_path = match resolve_within_root(self.root.as_deref(), &file_path).await {
+            Ok(p) => p,
+            Err(e) => return e,
+        };
+
+        let metadata = match fs::metadata(&file_path).await {
+            Ok(m) => m,
+            Err(e) => {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    return format!("Error: Path not found: {}", path);
+                }
+                return format!("Error: {}", e);
+            }
+        };
+
+        if metadata.is_dir() {
+            if !recursive {
+                let mut entries = match fs::read_dir(&file_path).await {
+                    Ok(e) => e,
+                    Err(e) => return format!("Error: {}", e),
+                };
+                let has_entries = matches!(entries.next_entry().await, Ok(Some(_)));
+                if has_entries {
+                    return format!(
+                        "Error: '{}' is a non-empty directory; pass recursive=true to delete it",
+                        path
+                    );
+                }
+                if let Err(e) = fs::remove_dir(&file_path).await {
+                    return format!("Error deleting directory: {}", e);
+                }
+            } else if let Err(e) = fs::remove_dir_all(&file_path).await {
+                return format!("Error deleting directory: {}", e);
+            }
+        } else if let Err(e) = fs::remove_file(&file_path).await {
+            return if e.kind() == std::io::ErrorKind::PermissionDenied {
+                format!("Error: Permission denied: {}", path)
+            } else {
+                format!("Error deleting file: {}", e)
+            };
+        }
+
+        format!("Successfully deleted {}", path)
+    }
+}
+
+#[pymethods]
+impl DeleteFileTool {
+    #[new]
+    #[pyo3(signature = (root=None))]
+    fn new(root: Option<PathBuf>) -> Self {
+        Self { root }
+    }
+
+    #[getter]
+    fn name(&self) -> &str {
+        "delete_file"
+    }
+
+    #[getter]
+    fn description(&self) -> &str {
+        Tool::description(self)
+    }
+
+    #[getter]
+    fn parameters(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let params = Tool::parameters(self);
+        let json_str = serde_json::to_string(&params)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let result = py.import("json")?.call_method1("loads", (json_str,))?;
+        Ok(result.into())
+    }
+
+    #[pyo3(signature = (path, recursive=false))]
+    fn execute<'py>(
+        &self,
+        py: Python<'py>,
+        path: String,
+        recursive: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
         let this = self.clone();
         future_into_py(py, async move {
             let mut params = HashMap::new();
-            params.insert("path".to_string(), path);
-            Ok(this.execute_inner(&params).await)
+            params.insert("path".to_string(), TypedValue::String(path));
+            params.insert("recursive".to_string(), TypedValue::Boolean(recursive));
+            Ok(this.execute_inner(&params, None).await)
         })
     }
 
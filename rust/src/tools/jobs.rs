@@ -0,0 +1,253 @@
+//! Background job manager for long-running shell commands.
+//!
+//! `ExecTool::execute` blocks until the command finishes, which is wrong
+//! for a dev server, watcher, or build that an agent wants to start and
+//! keep polling while it does other work. `JobManager` runs the same
+//! process-group-aware machinery from [`super::shell`] as a detached
+//! `tokio` task per job, so the caller gets a job id back immediately and
+//! can check on or stop the job later.
+
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use super::shell::{
+    parse_signal, resolve_cwd, spawn_streaming, CancelHandle, CommandPolicy, SpawnOptions,
+    StreamEvent,
+};
+
+/// Number of recent output lines a job keeps around for `tail` - older
+/// lines are dropped so a long-running dev server doesn't grow without
+/// bound.
+const RING_BUFFER_LINES: usize = 1000;
+
+type JobId = u64;
+
+/// Lifecycle state of a background job.
+#[pyclass(eq, eq_int)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JobState {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[pymethods]
+impl JobState {
+    fn __repr__(&self) -> &'static str {
+        match self {
+            JobState::Running => "JobState.Running",
+            JobState::Succeeded => "JobState.Succeeded",
+            JobState::Failed => "JobState.Failed",
+            JobState::Cancelled => "JobState.Cancelled",
+        }
+    }
+}
+
+/// Snapshot of one job's metadata, returned by `JobManager::list`/`status`.
+/// Does not include output - use `JobManager::tail` for that.
+#[pyclass]
+#[derive(Clone)]
+pub struct JobInfo {
+    #[pyo3(get)]
+    pub job_id: u64,
+    #[pyo3(get)]
+    pub command: String,
+    #[pyo3(get)]
+    pub working_dir: Option<String>,
+    #[pyo3(get)]
+    pub state: JobState,
+    #[pyo3(get)]
+    pub exit_code: Option<i32>,
+    #[pyo3(get)]
+    pub created_at: String,
+}
+
+#[pymethods]
+impl JobInfo {
+    fn __repr__(&self) -> String {
+        format!(
+            "JobInfo(job_id={}, command={:?}, state={:?})",
+            self.job_id, self.command, self.state
+        )
+    }
+}
+
+/// Shared state for one submitted job: its metadata plus accumulated
+/// output, updated in place by the task spawned for it in `submit` and
+/// read by `status`/`tail`/`list`.
+struct JobHandle {
+    info: JobInfo,
+    output: Vec<String>,
+    cancel: CancelHandle,
+    cancel_requested: Arc<AtomicBool>,
+}
+
+impl JobHandle {
+    fn push_line(&mut self, line: String) {
+        self.output.push(line);
+        if self.output.len() > RING_BUFFER_LINES {
+            let overflow = self.output.len() - RING_BUFFER_LINES;
+            self.output.drain(0..overflow);
+        }
+    }
+}
+
+/// Runs `ExecTool` commands as detached background jobs instead of
+/// blocking until they finish. Each `submit` gets a job id and a `tokio`
+/// task that drains its command's output into a ring buffer and updates
+/// its state in the shared job map; `list`/`status`/`tail`/`cancel` just
+/// read or signal that state.
+#[pyclass]
+#[derive(Clone)]
+pub struct JobManager {
+    jobs: Arc<Mutex<HashMap<JobId, JobHandle>>>,
+    next_id: Arc<AtomicU64>,
+    timeout_secs: u64,
+    /// Same `CommandPolicy` an operator would configure on `ExecTool` -
+    /// without this, `submit` would be a second, unpoliced way to run
+    /// arbitrary shell commands. `None` means unguarded, matching
+    /// `ExecTool`'s own "no policy configured" default.
+    policy: Option<CommandPolicy>,
+}
+
+#[pymethods]
+impl JobManager {
+    /// `timeout` bounds how long any one job may run before it's
+    /// terminated like a plain `exec` timeout would be (default one hour,
+    /// since jobs are expected to be long-running by design). `policy`, if
+    /// given, is applied to every `submit`'d command the same way it would
+    /// be applied to `ExecTool::execute`.
+    #[new]
+    #[pyo3(signature = (timeout=3600, policy=None))]
+    fn new(timeout: u64, policy: Option<CommandPolicy>) -> Self {
+        Self {
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            next_id: Arc::new(AtomicU64::new(1)),
+            timeout_secs: timeout,
+            policy,
+        }
+    }
+
+    /// Start `command` running in the background under `working_dir` (or
+    /// the process's current directory) and return its job id immediately,
+    /// without waiting for it to finish. Raises `RuntimeError` if a
+    /// `CommandPolicy` is configured and blocks `command`, the same check
+    /// `ExecTool::execute` applies before spawning.
+    #[pyo3(signature = (command, working_dir=None))]
+    fn submit(&self, command: String, working_dir: Option<String>) -> PyResult<u64> {
+        let (direct, env_allowlist) = match &self.policy {
+            Some(policy) => policy
+                .resolve(&command)
+                .map_err(pyo3::exceptions::PyRuntimeError::new_err)?,
+            None => (None, None),
+        };
+
+        let job_id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let cwd = resolve_cwd(working_dir.as_deref(), None);
+        let term_signal = parse_signal("SIGTERM");
+        let (mut rx, cancel) = spawn_streaming(
+            command.clone(),
+            cwd,
+            self.timeout_secs,
+            term_signal,
+            SpawnOptions {
+                direct,
+                env_allowlist,
+                ..Default::default()
+            },
+        );
+
+        let created_at = chrono::Utc::now()
+            .format("%Y-%m-%dT%H:%M:%S%.6f")
+            .to_string();
+        let cancel_requested = Arc::new(AtomicBool::new(false));
+
+        self.jobs.lock().insert(
+            job_id,
+            JobHandle {
+                info: JobInfo {
+                    job_id,
+                    command,
+                    working_dir,
+                    state: JobState::Running,
+                    exit_code: None,
+                    created_at,
+                },
+                output: Vec::new(),
+                cancel,
+                cancel_requested: cancel_requested.clone(),
+            },
+        );
+
+        let jobs = self.jobs.clone();
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                let mut jobs = jobs.lock();
+                let Some(handle) = jobs.get_mut(&job_id) else {
+                    continue;
+                };
+                match event {
+                    StreamEvent::Stdout(line) => handle.push_line(line),
+                    StreamEvent::Stderr(line) => handle.push_line(format!("STDERR: {}", line)),
+                    StreamEvent::Exit(code) => {
+                        handle.info.exit_code = Some(code);
+                        handle.info.state = if code == 0 {
+                            JobState::Succeeded
+                        } else {
+                            JobState::Failed
+                        };
+                    }
+                    StreamEvent::Error(msg) => {
+                        handle.push_line(msg);
+                        handle.info.state = if cancel_requested.load(Ordering::Relaxed) {
+                            JobState::Cancelled
+                        } else {
+                            JobState::Failed
+                        };
+                    }
+                }
+            }
+        });
+
+        Ok(job_id)
+    }
+
+    /// Snapshot every known job's metadata (running and finished).
+    fn list(&self) -> Vec<JobInfo> {
+        self.jobs.lock().values().map(|h| h.info.clone()).collect()
+    }
+
+    /// Snapshot one job's metadata, or `None` if `job_id` is unknown.
+    fn status(&self, job_id: u64) -> Option<JobInfo> {
+        self.jobs.lock().get(&job_id).map(|h| h.info.clone())
+    }
+
+    /// The last `n` accumulated output lines for `job_id` (stderr lines
+    /// prefixed `STDERR: `), or `None` if `job_id` is unknown.
+    fn tail(&self, job_id: u64, n: usize) -> Option<Vec<String>> {
+        self.jobs.lock().get(&job_id).map(|h| {
+            let start = h.output.len().saturating_sub(n);
+            h.output[start..].to_vec()
+        })
+    }
+
+    /// Ask a running job to stop: its whole process group gets `SIGTERM`,
+    /// escalating to `SIGKILL` if it's still alive after the grace period.
+    /// Returns `false` if `job_id` is unknown or already finished.
+    fn cancel(&self, job_id: u64) -> bool {
+        let jobs = self.jobs.lock();
+        match jobs.get(&job_id) {
+            Some(handle) if handle.info.state == JobState::Running => {
+                handle.cancel_requested.store(true, Ordering::Relaxed);
+                handle.cancel.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+}
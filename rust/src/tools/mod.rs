@@ -2,12 +2,18 @@
 
 pub mod base;
 pub mod filesystem;
+pub mod jobs;
 pub mod registry;
+pub mod script;
 pub mod shell;
 pub mod web;
 
 // Tool trait is used internally but not exported to Python
-pub use filesystem::{EditFileTool, ListDirTool, ReadFileTool, WriteFileTool};
+pub use filesystem::{
+    CopyFileTool, DeleteFileTool, EditFileTool, ListDirTool, MoveFileTool, ReadFileTool,
+    SearchFileTool, WriteFileTool,
+};
+pub use jobs::{JobInfo, JobManager, JobState};
 pub use registry::ToolRegistry;
-pub use shell::ExecTool;
+pub use shell::{CommandPolicy, ExecStream, ExecTool};
 pub use web::{WebFetchTool, WebSearchTool};
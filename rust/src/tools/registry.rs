@@ -1,16 +1,63 @@
 //! Tool registry for managing and executing tools.
 
+use futures::future::join_all;
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList};
-use pyo3_async_runtimes::tokio::future_into_py;
+use pyo3::types::{PyDict, PyList, PyTuple};
+use pyo3_async_runtimes::tokio::{future_into_py, into_future};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, Semaphore};
 
-use super::base::ToolSchema;
-use super::filesystem::{EditFileTool, ListDirTool, ReadFileTool, WriteFileTool};
+use std::path::PathBuf;
+
+use super::base::{ChunkSender, ClonablePyObject, Conversion, ToolSchema, TypedValue};
+use super::filesystem::{
+    CopyFileTool, DeleteFileTool, EditFileTool, ListDirTool, MoveFileTool, ReadFileTool,
+    SearchFileTool, WriteFileTool,
+};
+use super::script::ScriptTool;
 use super::shell::ExecTool;
 
+/// Convert a coerced tool parameter back into a native Python value (not
+/// just its string form) of its `TypedValue` variant. Python-defined tools
+/// declare no conversions, so the values they are passed are strings.
+fn typed_value_to_py(py: Python<'_>, value: &TypedValue) -> PyResult<PyObject> {
+    use pyo3::types::{PyBool, PyBytes};
+
+    match value {
+        TypedValue::String(s) => Ok(s.into_pyobject(py)?.into_any().unbind()),
+        TypedValue::Bytes(b) => Ok(PyBytes::new(py, b).into_any().unbind()),
+        TypedValue::Integer(i) => Ok(i.into_pyobject(py)?.into_any().unbind()),
+        TypedValue::Float(f) => Ok(f.into_pyobject(py)?.into_any().unbind()),
+        TypedValue::Boolean(b) => Ok(PyBool::new(py, *b).to_owned().into_any().unbind()),
+        TypedValue::Timestamp(t) => Ok(t.into_pyobject(py)?.into_any().unbind()),
+    }
+}
+
+/// Coerce a tool-call parameter dict into typed values using the tool's
+/// declared [`Conversion`] per key (defaulting to `String`). Returns the
+/// first `parameter '<k>' expected <type>` failure encountered.
+fn coerce_params(
+    params: &Bound<'_, PyDict>,
+    conversions: &HashMap<String, Conversion>,
+) -> PyResult<Result<HashMap<String, TypedValue>, String>> {
+    let mut result = HashMap::new();
+    for (key, value) in params.iter() {
+        let key_str: String = key.extract()?;
+        let conversion = conversions
+            .get(&key_str)
+            .cloned()
+            .unwrap_or(Conversion::String);
+        match TypedValue::coerce(&key_str, &value, &conversion) {
+            Ok(typed) => {
+                result.insert(key_str, typed);
+            }
+            Err(msg) => return Ok(Err(msg)),
+        }
+    }
+    Ok(Ok(result))
+}
+
 /// Internal enum to hold different tool types.
 #[derive(Clone)]
 enum ToolType {
@@ -18,7 +65,21 @@ enum ToolType {
     WriteFile(WriteFileTool),
     EditFile(EditFileTool),
     ListDir(ListDirTool),
+    SearchFile(SearchFileTool),
+    CopyFile(CopyFileTool),
+    MoveFile(MoveFileTool),
+    DeleteFile(DeleteFileTool),
     Exec(ExecTool),
+    Script(ScriptTool),
+    /// A tool implemented entirely in Python (web/message/spawn and any
+    /// other tool that doesn't map onto a native Rust struct). `schema` is
+    /// the OpenAI-format dict returned by the object's `to_schema()` at
+    /// registration time; `execute` is re-resolved and awaited live.
+    Python {
+        callable: Arc<PyObject>,
+        name: String,
+        schema: ClonablePyObject,
+    },
 }
 
 impl ToolType {
@@ -29,7 +90,13 @@ impl ToolType {
             ToolType::WriteFile(t) => t.tool_name(),
             ToolType::EditFile(t) => t.tool_name(),
             ToolType::ListDir(t) => t.tool_name(),
+            ToolType::SearchFile(t) => t.tool_name(),
+            ToolType::CopyFile(t) => t.tool_name(),
+            ToolType::MoveFile(t) => t.tool_name(),
+            ToolType::DeleteFile(t) => t.tool_name(),
             ToolType::Exec(t) => t.tool_name(),
+            ToolType::Script(t) => t.tool_name(),
+            ToolType::Python { name, .. } => name,
         }
     }
 
@@ -39,21 +106,95 @@ impl ToolType {
             ToolType::WriteFile(t) => t.to_schema(py),
             ToolType::EditFile(t) => t.to_schema(py),
             ToolType::ListDir(t) => t.to_schema(py),
+            ToolType::SearchFile(t) => t.to_schema(py),
+            ToolType::CopyFile(t) => t.to_schema(py),
+            ToolType::MoveFile(t) => t.to_schema(py),
+            ToolType::DeleteFile(t) => t.to_schema(py),
             ToolType::Exec(t) => t.to_schema(py),
+            ToolType::Script(t) => t.to_schema(py),
+            ToolType::Python { name, schema, .. } => {
+                let function = schema.get().bind(py).get_item("function")?;
+                let description: String = function.get_item("description")?.extract()?;
+                let parameters = function.get_item("parameters")?.unbind();
+                Ok(ToolSchema {
+                    name: name.clone(),
+                    description,
+                    parameters: ClonablePyObject::new(parameters),
+                })
+            }
+        }
+    }
+
+    fn conversions(&self) -> HashMap<String, Conversion> {
+        match self {
+            ToolType::ReadFile(t) => t.conversions(),
+            ToolType::WriteFile(t) => t.conversions(),
+            ToolType::EditFile(t) => t.conversions(),
+            ToolType::ListDir(t) => t.conversions(),
+            ToolType::SearchFile(t) => t.conversions(),
+            ToolType::CopyFile(t) => t.conversions(),
+            ToolType::MoveFile(t) => t.conversions(),
+            ToolType::DeleteFile(t) => t.conversions(),
+            ToolType::Exec(t) => t.conversions(),
+            ToolType::Script(t) => t.conversions(),
+            // Python tools declare no conversions, so every param is
+            // coerced as a string (see `TypedValue::coerce`), whatever
+            // type the model passed in.
+            ToolType::Python { .. } => HashMap::new(),
         }
     }
 
-    async fn execute(&self, params: HashMap<String, String>) -> String {
+    async fn execute(
+        &self,
+        params: HashMap<String, TypedValue>,
+        on_chunk: Option<ChunkSender>,
+    ) -> String {
         match self {
-            ToolType::ReadFile(t) => t.execute_inner(&params).await,
-            ToolType::WriteFile(t) => t.execute_inner(&params).await,
-            ToolType::EditFile(t) => t.execute_inner(&params).await,
-            ToolType::ListDir(t) => t.execute_inner(&params).await,
-            ToolType::Exec(t) => t.execute_inner(&params).await,
+            ToolType::ReadFile(t) => t.execute_inner(&params, on_chunk).await,
+            ToolType::WriteFile(t) => t.execute_inner(&params, on_chunk).await,
+            ToolType::EditFile(t) => t.execute_inner(&params, on_chunk).await,
+            ToolType::ListDir(t) => t.execute_inner(&params, on_chunk).await,
+            ToolType::SearchFile(t) => t.execute_inner(&params, on_chunk).await,
+            ToolType::CopyFile(t) => t.execute_inner(&params, on_chunk).await,
+            ToolType::MoveFile(t) => t.execute_inner(&params, on_chunk).await,
+            ToolType::DeleteFile(t) => t.execute_inner(&params, on_chunk).await,
+            ToolType::Exec(t) => t.execute_inner(&params, on_chunk).await,
+            ToolType::Script(t) => t.execute_inner(&params, on_chunk).await,
+            ToolType::Python { callable, .. } => {
+                // Python tools don't support `on_chunk` streaming yet; call
+                // the coroutine and bridge it into the Rust async world.
+                let pending = Python::with_gil(|py| -> PyResult<_> {
+                    let kwargs = PyDict::new(py);
+                    for (key, value) in &params {
+                        kwargs.set_item(key, typed_value_to_py(py, value)?)?;
+                    }
+                    let coro = callable.bind(py).call_method1("execute", (kwargs,))?;
+                    into_future(coro)
+                });
+                let result = match pending {
+                    Ok(fut) => fut.await,
+                    Err(e) => return format!("Error: {}", e),
+                };
+                match result {
+                    Ok(value) => Python::with_gil(|py| {
+                        value
+                            .extract::<String>(py)
+                            .unwrap_or_else(|_| value.bind(py).str().map(|s| s.to_string()).unwrap_or_default())
+                    }),
+                    Err(e) => format!("Error: {}", e),
+                }
+            }
         }
     }
 }
 
+/// A resolved batch entry: either a cloned tool ready to run, or an error
+/// message to return in that slot without ever calling the tool.
+enum BatchItem {
+    Ready(ToolType, HashMap<String, TypedValue>),
+    Error(String),
+}
+
 /// Registry for agent tools.
 ///
 /// Allows dynamic registration and execution of tools.
@@ -95,12 +236,54 @@ impl ToolRegistry {
         tools.insert(tool.tool_name().to_string(), ToolType::ListDir(tool));
     }
 
+    /// Register a SearchFileTool.
+    fn register_search_file(&self, tool: SearchFileTool) {
+        let mut tools = futures::executor::block_on(self.tools.write());
+        tools.insert(tool.tool_name().to_string(), ToolType::SearchFile(tool));
+    }
+
+    /// Register a CopyFileTool.
+    fn register_copy_file(&self, tool: CopyFileTool) {
+        let mut tools = futures::executor::block_on(self.tools.write());
+        tools.insert(tool.tool_name().to_string(), ToolType::CopyFile(tool));
+    }
+
+    /// Register a MoveFileTool.
+    fn register_move_file(&self, tool: MoveFileTool) {
+        let mut tools = futures::executor::block_on(self.tools.write());
+        tools.insert(tool.tool_name().to_string(), ToolType::MoveFile(tool));
+    }
+
+    /// Register a DeleteFileTool.
+    fn register_delete_file(&self, tool: DeleteFileTool) {
+        let mut tools = futures::executor::block_on(self.tools.write());
+        tools.insert(tool.tool_name().to_string(), ToolType::DeleteFile(tool));
+    }
+
     /// Register an ExecTool.
     fn register_exec(&self, tool: ExecTool) {
         let mut tools = futures::executor::block_on(self.tools.write());
         tools.insert(tool.tool_name().to_string(), ToolType::Exec(tool));
     }
 
+    /// Register a user-defined tool scripted in Rhai. `schema_json` is
+    /// `{"description": ..., "parameters": <json schema>}`; `body` is
+    /// compiled once and re-evaluated fresh on every call.
+    #[pyo3(signature = (name, schema_json, body, sandbox_root=None))]
+    fn register_script(
+        &self,
+        name: String,
+        schema_json: String,
+        body: String,
+        sandbox_root: Option<PathBuf>,
+    ) -> PyResult<()> {
+        let tool = ScriptTool::compile(name.clone(), &schema_json, &body, sandbox_root)
+            .map_err(pyo3::exceptions::PyValueError::new_err)?;
+        let mut tools = futures::executor::block_on(self.tools.write());
+        tools.insert(name, ToolType::Script(tool));
+        Ok(())
+    }
+
     /// Register any tool (generic method for Python compatibility).
     fn register(&self, tool: &Bound<'_, PyAny>) -> PyResult<()> {
         // Try to extract each tool type
@@ -120,13 +303,47 @@ impl ToolRegistry {
             self.register_list_dir(t);
             return Ok(());
         }
+        if let Ok(t) = tool.extract::<SearchFileTool>() {
+            self.register_search_file(t);
+            return Ok(());
+        }
+        if let Ok(t) = tool.extract::<CopyFileTool>() {
+            self.register_copy_file(t);
+            return Ok(());
+        }
+        if let Ok(t) = tool.extract::<MoveFileTool>() {
+            self.register_move_file(t);
+            return Ok(());
+        }
+        if let Ok(t) = tool.extract::<DeleteFileTool>() {
+            self.register_delete_file(t);
+            return Ok(());
+        }
         if let Ok(t) = tool.extract::<ExecTool>() {
             self.register_exec(t);
             return Ok(());
         }
 
-        // For Python-based tools (web, message, spawn), we need to store them differently
-        // For now, just ignore them - they'll be handled by Python fallback
+        // Anything else (web/message/spawn, or any other Python-defined
+        // tool) is registered as a `ToolType::Python` instead of being
+        // dropped, so it shows up in `get_definitions`/`tool_names` and is
+        // callable through `execute`/`execute_batch` like a native tool.
+        self.register_python(tool)
+    }
+
+    /// Register a tool implemented entirely in Python. `tool` must expose a
+    /// `name` attribute, a `to_schema()` method returning the OpenAI-format
+    /// function dict, and an async `execute(params)` coroutine.
+    fn register_python(&self, tool: &Bound<'_, PyAny>) -> PyResult<()> {
+        let name: String = tool.getattr("name")?.extract()?;
+        let schema = tool.call_method0("to_schema")?.unbind();
+        let tool_type = ToolType::Python {
+            callable: Arc::new(tool.clone().unbind()),
+            name: name.clone(),
+            schema: ClonablePyObject::new(schema),
+        };
+        let mut tools = futures::executor::block_on(self.tools.write());
+        tools.insert(name, tool_type);
         Ok(())
     }
 
@@ -162,41 +379,112 @@ impl ToolRegistry {
     }
 
     /// Execute a tool by name with given parameters.
+    ///
+    /// Parameters are coerced according to the tool's declared parameter
+    /// [`Conversion`]s before the tool is invoked; a type mismatch returns
+    /// `Error: parameter '<k>' expected <type>` without running the tool.
+    ///
+    /// If `on_chunk` is given, it is called with each incremental progress
+    /// chunk a tool reports (e.g. `exec` stdout/stderr lines, `write_file`
+    /// bytes-written counts) while the tool is still running; the full
+    /// accumulated result is still returned once the tool completes.
+    #[pyo3(signature = (name, params, on_chunk=None))]
     fn execute<'py>(
         &self,
         py: Python<'py>,
         name: String,
         params: &Bound<'py, PyDict>,
+        on_chunk: Option<PyObject>,
     ) -> PyResult<Bound<'py, PyAny>> {
-        let tools = self.tools.clone();
-
-        // Extract params to a HashMap<String, String>
-        let mut param_map: HashMap<String, String> = HashMap::new();
-        for (key, value) in params.iter() {
-            let key_str: String = key.extract()?;
-            // Try to extract as string, or convert to string
-            let value_str: String = if let Ok(s) = value.extract::<String>() {
-                s
-            } else if let Ok(i) = value.extract::<i64>() {
-                i.to_string()
-            } else if let Ok(b) = value.extract::<bool>() {
-                b.to_string()
-            } else {
-                value.str()?.to_string()
-            };
-            param_map.insert(key_str, value_str);
-        }
+        let batch_item = {
+            let tools_guard = futures::executor::block_on(self.tools.read());
+            match tools_guard.get(&name) {
+                Some(tool) => match coerce_params(params, &tool.conversions())? {
+                    Ok(map) => BatchItem::Ready(tool.clone(), map),
+                    Err(msg) => BatchItem::Error(format!("Error: {}", msg)),
+                },
+                None => BatchItem::Error(format!("Error: Tool '{}' not found", name)),
+            }
+        };
 
         future_into_py(py, async move {
-            let tools_guard = tools.read().await;
-
-            if let Some(tool) = tools_guard.get(&name) {
-                let tool = tool.clone();
-                drop(tools_guard); // Release the lock before executing
-                Ok(tool.execute(param_map).await)
-            } else {
-                Ok(format!("Error: Tool '{}' not found", name))
+            match batch_item {
+                BatchItem::Ready(tool, params) => match on_chunk {
+                    Some(callback) => {
+                        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+                        let forward = tokio::spawn(async move {
+                            while let Some(chunk) = rx.recv().await {
+                                let _ = Python::with_gil(|py| callback.call1(py, (chunk,)));
+                            }
+                        });
+                        let result = tool.execute(params, Some(tx)).await;
+                        let _ = forward.await;
+                        Ok(result)
+                    }
+                    None => Ok(tool.execute(params, None).await),
+                },
+                BatchItem::Error(msg) => Ok(msg),
+            }
+        })
+    }
+
+    /// Execute several tool calls concurrently, preserving input order.
+    ///
+    /// `calls` is a list of `(name, params)` tuples. Unknown tool names
+    /// produce an `Error: Tool '<name>' not found` entry in their slot
+    /// instead of aborting the whole batch. Concurrency is bounded by
+    /// `max_concurrency` (default `num_cpus::get()`).
+    #[pyo3(signature = (calls, max_concurrency=None))]
+    fn execute_batch<'py>(
+        &self,
+        py: Python<'py>,
+        calls: &Bound<'py, PyList>,
+        max_concurrency: Option<usize>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        // Resolve each call's tool (cloned), coerce its params, and acquire
+        // the read lock only once for the whole batch.
+        let mut requests: Vec<BatchItem> = Vec::with_capacity(calls.len());
+        {
+            let tools_guard = futures::executor::block_on(self.tools.read());
+            for item in calls.iter() {
+                let tuple = item.downcast::<PyTuple>()?;
+                if tuple.len() != 2 {
+                    return Err(pyo3::exceptions::PyValueError::new_err(
+                        "Each call must be a (name, params) tuple",
+                    ));
+                }
+                let name: String = tuple.get_item(0)?.extract()?;
+                let params = tuple.get_item(1)?;
+                let params_dict = params.downcast::<PyDict>()?;
+
+                let batch_item = match tools_guard.get(&name) {
+                    Some(tool) => match coerce_params(params_dict, &tool.conversions())? {
+                        Ok(map) => BatchItem::Ready(tool.clone(), map),
+                        Err(msg) => BatchItem::Error(format!("Error: {}", msg)),
+                    },
+                    None => BatchItem::Error(format!("Error: Tool '{}' not found", name)),
+                };
+                requests.push(batch_item);
             }
+        } // Release the lock before executing
+
+        let limit = max_concurrency.unwrap_or_else(num_cpus::get).max(1);
+
+        future_into_py(py, async move {
+            let semaphore = Arc::new(Semaphore::new(limit));
+
+            let futures = requests.into_iter().map(|item| {
+                let semaphore = semaphore.clone();
+                async move {
+                    let _permit = semaphore.acquire().await;
+                    match item {
+                        BatchItem::Ready(tool, params) => tool.execute(params, None).await,
+                        BatchItem::Error(msg) => msg,
+                    }
+                }
+            });
+
+            Ok(join_all(futures).await)
         })
     }
 
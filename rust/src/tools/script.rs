@@ -0,0 +1,188 @@
+//! User-defined tools scripted with an embedded Rhai interpreter.
+//!
+//! Unlike the built-in tools, a [`ScriptTool`] is never a `#[pyclass]` on
+//! its own - it is compiled and registered purely from a name, a JSON
+//! schema, and a script body via `ToolRegistry::register_script`, with no
+//! Rust recompile or Python glue required.
+
+use pyo3::prelude::*;
+use rhai::{Dynamic, Engine, Scope, AST};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::base::{ChunkSender, ClonablePyObject, Conversion, ToolSchema, TypedValue};
+
+/// A tool whose behavior is defined entirely by a compiled Rhai script.
+#[derive(Clone)]
+pub struct ScriptTool {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+    ast: Arc<AST>,
+    sandbox_root: Option<PathBuf>,
+}
+
+impl ScriptTool {
+    /// Compile `body` once for `name`, described by `schema_json`
+    /// (`{"description": ..., "parameters": <json schema>}`).
+    pub fn compile(
+        name: String,
+        schema_json: &str,
+        body: &str,
+        sandbox_root: Option<PathBuf>,
+    ) -> Result<Self, String> {
+        let schema: serde_json::Value = serde_json::from_str(schema_json)
+            .map_err(|e| format!("Invalid schema JSON: {}", e))?;
+        let description = schema
+            .get("description")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let parameters = schema.get("parameters").cloned().unwrap_or(schema);
+
+        let engine = build_engine(None);
+        let ast = engine
+            .compile(body)
+            .map_err(|e| format!("Failed to compile script: {}", e))?;
+
+        Ok(ScriptTool {
+            name,
+            description,
+            parameters,
+            ast: Arc::new(ast),
+            sandbox_root,
+        })
+    }
+
+    pub fn tool_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn to_schema(&self, py: Python<'_>) -> PyResult<ToolSchema> {
+        let params_str = serde_json::to_string(&self.parameters)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let params_obj = py
+            .import("json")?
+            .call_method1("loads", (params_str,))?
+            .unbind();
+
+        Ok(ToolSchema {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            parameters: ClonablePyObject::new(params_obj),
+        })
+    }
+
+    /// Derive a [`Conversion`] per declared parameter from the JSON schema's
+    /// `properties.*.type`, so script tools get the same typed coercion as
+    /// the built-ins instead of always seeing strings.
+    pub fn conversions(&self) -> HashMap<String, Conversion> {
+        let mut result = HashMap::new();
+        if let Some(props) = self.parameters.get("properties").and_then(|v| v.as_object()) {
+            for (key, prop_schema) in props {
+                let conversion = match prop_schema.get("type").and_then(|v| v.as_str()) {
+                    Some("integer") => Conversion::Integer,
+                    Some("number") => Conversion::Float,
+                    Some("boolean") => Conversion::Boolean,
+                    _ => Conversion::String,
+                };
+                result.insert(key.clone(), conversion);
+            }
+        }
+        result
+    }
+
+    pub async fn execute_inner(
+        &self,
+        params: &HashMap<String, TypedValue>,
+        _on_chunk: Option<ChunkSender>,
+    ) -> String {
+        let ast = self.ast.clone();
+        let sandbox_root = self.sandbox_root.clone();
+        let params = params.clone();
+
+        // rhai's evaluation is synchronous and CPU-bound; run it on a
+        // blocking thread so a slow or looping script doesn't stall the
+        // tokio executor the rest of the registry relies on.
+        let result = tokio::task::spawn_blocking(move || {
+            let engine = build_engine(sandbox_root);
+            let mut scope = Scope::new();
+            for (key, value) in &params {
+                scope.push(key.clone(), typed_value_to_dynamic(value));
+            }
+            match engine.eval_ast_with_scope::<Dynamic>(&mut scope, &ast) {
+                Ok(value) => value.to_string(),
+                Err(e) => format!("Error: script failed: {}", e),
+            }
+        })
+        .await;
+
+        result.unwrap_or_else(|e| format!("Error: script panicked: {}", e))
+    }
+}
+
+fn typed_value_to_dynamic(value: &TypedValue) -> Dynamic {
+    match value {
+        TypedValue::String(s) => Dynamic::from(s.clone()),
+        TypedValue::Bytes(b) => Dynamic::from(String::from_utf8_lossy(b).to_string()),
+        TypedValue::Integer(i) => Dynamic::from(*i),
+        TypedValue::Float(f) => Dynamic::from(*f),
+        TypedValue::Boolean(b) => Dynamic::from(*b),
+        TypedValue::Timestamp(t) => Dynamic::from(*t),
+    }
+}
+
+/// Build a Rhai engine exposing only the host functions scripts need,
+/// with operations capped so a buggy or hostile script can't hang the
+/// runtime, and no file/module imports.
+fn build_engine(sandbox_root: Option<PathBuf>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(500_000);
+    engine.disable_symbol("import");
+
+    let root = sandbox_root.clone();
+    engine.register_fn("path_exists", move |path: &str| -> bool {
+        resolve_sandboxed(&root, path)
+            .map(|p| p.exists())
+            .unwrap_or(false)
+    });
+
+    let root = sandbox_root.clone();
+    engine.register_fn("is_file", move |path: &str| -> bool {
+        resolve_sandboxed(&root, path)
+            .map(|p| p.is_file())
+            .unwrap_or(false)
+    });
+
+    engine.register_fn("read_to_string", move |path: &str| -> String {
+        resolve_sandboxed(&sandbox_root, path)
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .unwrap_or_default()
+    });
+
+    engine
+}
+
+/// Resolve `path` against `sandbox_root` (when set), rejecting anything
+/// that escapes it - the same confinement the filesystem tools apply.
+fn resolve_sandboxed(sandbox_root: &Option<PathBuf>, path: &str) -> Option<PathBuf> {
+    let candidate = PathBuf::from(path);
+    match sandbox_root {
+        None => Some(candidate),
+        Some(root) => {
+            let joined = if candidate.is_absolute() {
+                candidate
+            } else {
+                root.join(candidate)
+            };
+            let canonical_root = root.canonicalize().ok()?;
+            let canonical = joined.canonicalize().unwrap_or(joined);
+            if canonical.starts_with(&canonical_root) {
+                Some(canonical)
+            } else {
+                None
+            }
+        }
+    }
+}
@@ -1,14 +1,684 @@
 //! Shell execution tool.
 
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use pyo3_async_runtimes::tokio::future_into_py;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
+use tokio::sync::{mpsc, Mutex};
 use tokio::time::timeout;
 
-use super::base::{object_schema, string_prop, Tool, ToolSchema};
+use super::base::{
+    int_prop, object_schema, string_prop, ChunkSender, Conversion, Tool, ToolSchema, TypedValue,
+};
+
+/// One incremental event from a streamed command, as produced by
+/// [`spawn_streaming`] and consumed by both `ExecTool::execute` (which
+/// collects every event into one final string) and `ExecTool::execute_stream`
+/// (which hands each event to Python as it arrives).
+#[derive(Debug, Clone)]
+pub(crate) enum StreamEvent {
+    Stdout(String),
+    Stderr(String),
+    Exit(i32),
+    Error(String),
+}
+
+/// Resolve the working directory for a command: the per-call `working_dir`
+/// if given, else the tool instance's default, else the process's cwd.
+pub(crate) fn resolve_cwd(working_dir: Option<&str>, instance_default: Option<&str>) -> PathBuf {
+    working_dir
+        .or(instance_default)
+        .map(|s| {
+            if let Some(stripped) = s.strip_prefix("~/") {
+                if let Some(home) = dirs::home_dir() {
+                    return home.join(stripped);
+                }
+            }
+            PathBuf::from(s)
+        })
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")))
+}
+
+/// Build the shell command that runs `command`, placing it in its own
+/// process group (Unix: a new session via `setsid`) so the whole tree it may
+/// fork can be signalled together instead of just the immediate child.
+fn new_shell_command(command: &str) -> Command {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let mut c = Command::new("sh");
+        c.args(["-c", command]);
+        unsafe {
+            c.pre_exec(|| {
+                libc::setsid();
+                Ok(())
+            });
+        }
+        c
+    }
+    #[cfg(not(unix))]
+    {
+        let mut c = Command::new("cmd");
+        c.args(["/C", command]);
+        c
+    }
+}
+
+/// Build a `Command` that runs `program` with `args` directly, with no
+/// shell involved - used when a [`CommandPolicy`] forbids shell
+/// metacharacters and the command doesn't need any. Still placed in its
+/// own process group like [`new_shell_command`], so timeout/cancellation
+/// can signal the whole tree the same way either path spawns it.
+fn new_direct_command(program: &str, args: &[String]) -> Command {
+    let mut c = Command::new(program);
+    c.args(args);
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        unsafe {
+            c.pre_exec(|| {
+                libc::setsid();
+                Ok(())
+            });
+        }
+    }
+    c
+}
+
+/// Resolve a termination signal name (`"SIGTERM"`, `"SIGKILL"`, ...) to its
+/// numeric value, defaulting to `SIGTERM` for anything unrecognized. Ignored
+/// on non-Unix targets, where there is no process-group signal to send.
+#[cfg(unix)]
+pub(crate) fn parse_signal(name: &str) -> i32 {
+    match name.to_uppercase().as_str() {
+        "SIGKILL" | "KILL" => libc::SIGKILL,
+        "SIGINT" | "INT" => libc::SIGINT,
+        "SIGHUP" | "HUP" => libc::SIGHUP,
+        "SIGQUIT" | "QUIT" => libc::SIGQUIT,
+        _ => libc::SIGTERM,
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn parse_signal(_name: &str) -> i32 {
+    0
+}
+
+/// Terminate `child`'s whole process group: send `term_signal` (Unix) or
+/// request cooperative termination (Windows), give it a short grace period
+/// to exit on its own, then escalate to an unconditional kill of the group.
+async fn terminate(child: &mut tokio::process::Child, term_signal: i32) {
+    #[cfg(unix)]
+    {
+        if let Some(pid) = child.id() {
+            unsafe {
+                libc::kill(-(pid as i32), term_signal);
+            }
+        }
+
+        let grace = tokio::time::sleep(Duration::from_secs(2));
+        tokio::pin!(grace);
+        tokio::select! {
+            _ = child.wait() => {}
+            _ = &mut grace => {
+                if let Some(pid) = child.id() {
+                    unsafe {
+                        libc::kill(-(pid as i32), libc::SIGKILL);
+                    }
+                }
+                let _ = child.wait().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = term_signal;
+        let _ = child.kill().await;
+    }
+}
+
+/// Lets a caller holding a live [`ExecStream`] ask an in-flight command to
+/// stop early, the same way a timeout would: the whole process group gets
+/// `term_signal`, then `SIGKILL` if it's still alive after the grace period.
+#[derive(Clone)]
+pub(crate) struct CancelHandle {
+    tx: mpsc::UnboundedSender<()>,
+}
+
+impl CancelHandle {
+    pub(crate) fn cancel(&self) {
+        let _ = self.tx.send(());
+    }
+}
+
+/// Optional, infrequently-needed extras for [`spawn_streaming`], bundled
+/// into one struct rather than growing that function's argument list
+/// further - most callers just want `SpawnOptions::default()`.
+#[derive(Default)]
+pub(crate) struct SpawnOptions {
+    /// `(program, args)` to exec directly instead of going through the
+    /// shell - see [`ExecPlan::Direct`].
+    pub direct: Option<(String, Vec<String>)>,
+    /// Replaces the child's inherited environment with exactly these
+    /// variables before `env_overrides` is applied - see
+    /// [`CommandPolicy::filtered_env`].
+    pub env_allowlist: Option<Vec<(String, String)>>,
+    /// Extra variables merged over the child's environment (the full
+    /// parent environment, or `env_allowlist` if also set).
+    pub env_overrides: Option<Vec<(String, String)>>,
+    /// Written to the child's stdin and then closed, so commands like
+    /// `jq` can read piped input instead of hanging on an inherited tty.
+    pub stdin: Option<String>,
+}
+
+/// Spawn `command` in `cwd`, streaming stdout/stderr lines (read concurrently
+/// on separate tasks) as [`StreamEvent`]s over the returned channel, followed
+/// by a final `Exit` (or `Error`, on spawn/wait failure, cancellation, or
+/// timeout) event. On timeout or an explicit [`CancelHandle::cancel`], the
+/// command's entire process group is signalled and reaped rather than left
+/// running - see [`terminate`].
+pub(crate) fn spawn_streaming(
+    command: String,
+    cwd: PathBuf,
+    timeout_secs: u64,
+    term_signal: i32,
+    opts: SpawnOptions,
+) -> (mpsc::UnboundedReceiver<StreamEvent>, CancelHandle) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let (cancel_tx, mut cancel_rx) = mpsc::unbounded_channel::<()>();
+
+    tokio::spawn(async move {
+        let mut cmd = match &opts.direct {
+            Some((program, args)) => new_direct_command(program, args),
+            None => new_shell_command(&command),
+        };
+        cmd.current_dir(&cwd);
+        if let Some(allowed) = opts.env_allowlist {
+            cmd.env_clear();
+            cmd.envs(allowed);
+        }
+        if let Some(overrides) = opts.env_overrides {
+            cmd.envs(overrides);
+        }
+        cmd.stdout(std::process::Stdio::piped());
+        cmd.stderr(std::process::Stdio::piped());
+        if opts.stdin.is_some() {
+            cmd.stdin(std::process::Stdio::piped());
+        }
+
+        let mut child = match cmd.spawn() {
+            Ok(c) => c,
+            Err(e) => {
+                let _ = tx.send(StreamEvent::Error(format!("Error executing command: {}", e)));
+                return;
+            }
+        };
+
+        if let Some(data) = opts.stdin {
+            if let Some(mut child_stdin) = child.stdin.take() {
+                use tokio::io::AsyncWriteExt;
+                let _ = child_stdin.write_all(data.as_bytes()).await;
+                drop(child_stdin);
+            }
+        }
+
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+
+        let stdout_tx = tx.clone();
+        let stdout_task = tokio::spawn(async move {
+            if let Some(out) = stdout {
+                let mut lines = BufReader::new(out).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let _ = stdout_tx.send(StreamEvent::Stdout(line));
+                }
+            }
+        });
+
+        let stderr_tx = tx.clone();
+        let stderr_task = tokio::spawn(async move {
+            if let Some(err) = stderr {
+                let mut lines = BufReader::new(err).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let _ = stderr_tx.send(StreamEvent::Stderr(line));
+                }
+            }
+        });
+
+        tokio::select! {
+            result = child.wait() => {
+                match result {
+                    Ok(status) => {
+                        let _ = tx.send(StreamEvent::Exit(status.code().unwrap_or(-1)));
+                    }
+                    Err(e) => {
+                        let _ = tx.send(StreamEvent::Error(format!("Error executing command: {}", e)));
+                    }
+                }
+            }
+            _ = cancel_rx.recv() => {
+                terminate(&mut child, term_signal).await;
+                let _ = tx.send(StreamEvent::Error("Error: Command cancelled".to_string()));
+            }
+            _ = tokio::time::sleep(Duration::from_secs(timeout_secs)) => {
+                terminate(&mut child, term_signal).await;
+                let _ = tx.send(StreamEvent::Error(format!(
+                    "Error: Command timed out after {} seconds",
+                    timeout_secs
+                )));
+            }
+        }
+
+        let _ = stdout_task.await;
+        let _ = stderr_task.await;
+    });
+
+    (rx, CancelHandle { tx: cancel_tx })
+}
+
+/// Convert one [`StreamEvent`] into the `{stream, data}` / `{exit_code}` /
+/// `{error}` dict shape `execute_stream` yields to Python.
+fn event_to_py(py: Python<'_>, event: StreamEvent) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    match event {
+        StreamEvent::Stdout(data) => {
+            dict.set_item("stream", "stdout")?;
+            dict.set_item("data", data)?;
+        }
+        StreamEvent::Stderr(data) => {
+            dict.set_item("stream", "stderr")?;
+            dict.set_item("data", data)?;
+        }
+        StreamEvent::Exit(code) => {
+            dict.set_item("exit_code", code)?;
+        }
+        StreamEvent::Error(msg) => {
+            dict.set_item("error", msg)?;
+        }
+    }
+    Ok(dict.into())
+}
+
+/// Async iterator returned by `ExecTool::execute_stream`, yielding one
+/// `{stream, data}` dict per output line, then a final `{exit_code}` (or
+/// `{error}`) dict before the iterator is exhausted.
+#[pyclass]
+pub struct ExecStream {
+    rx: Arc<Mutex<mpsc::UnboundedReceiver<StreamEvent>>>,
+    cancel: CancelHandle,
+}
+
+#[pymethods]
+impl ExecStream {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let rx = self.rx.clone();
+        future_into_py(py, async move {
+            let mut guard = rx.lock().await;
+            match guard.recv().await {
+                Some(event) => Python::with_gil(|py| event_to_py(py, event)),
+                None => Err(pyo3::exceptions::PyStopAsyncIteration::new_err(())),
+            }
+        })
+    }
+
+    /// Ask the in-flight command to stop: its whole process group gets the
+    /// tool's configured termination signal, escalating to SIGKILL if it's
+    /// still alive after the grace period.
+    fn cancel(&self) {
+        self.cancel.cancel();
+    }
+}
+
+/// Run `command` attached to a pseudo-terminal of `rows` x `cols` instead of
+/// plain pipes, so TTY-dependent programs (colorized output, `isatty()`
+/// checks, REPLs) behave as they would in an interactive shell. Runs
+/// synchronously (the `portable-pty` API is blocking) - call from within
+/// `spawn_blocking`. Polls `cancel` between reads so the caller can tear the
+/// PTY down on timeout instead of leaving it running after the task gives up.
+fn run_pty(
+    command: String,
+    cwd: PathBuf,
+    rows: u16,
+    cols: u16,
+    cancel: Arc<AtomicBool>,
+    env_allowlist: Option<Vec<(String, String)>>,
+) -> Result<(String, i32), String> {
+    use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+    use std::io::Read;
+
+    let pty_system = native_pty_system();
+    let pair = pty_system
+        .openpty(PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(|e| format!("Error: failed to allocate pty: {}", e))?;
+
+    let mut builder = if cfg!(target_os = "windows") {
+        let mut b = CommandBuilder::new("cmd");
+        b.args(["/C", &command]);
+        b
+    } else {
+        let mut b = CommandBuilder::new("sh");
+        b.args(["-c", &command]);
+        b
+    };
+    builder.cwd(&cwd);
+    if let Some(allowed) = env_allowlist {
+        builder.env_clear();
+        for (key, value) in allowed {
+            builder.env(key, value);
+        }
+    }
+
+    let mut child = pair
+        .slave
+        .spawn_command(builder)
+        .map_err(|e| format!("Error executing command: {}", e))?;
+    drop(pair.slave);
+
+    let mut reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| format!("Error: {}", e))?;
+
+    let mut output = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("Error: Command timed out".to_string());
+        }
+
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                // Drain whatever output was still buffered when it exited.
+                while let Ok(n) = reader.read(&mut buf) {
+                    if n == 0 {
+                        break;
+                    }
+                    output.extend_from_slice(&buf[..n]);
+                }
+                return Ok((
+                    String::from_utf8_lossy(&output).into_owned(),
+                    status.exit_code() as i32,
+                ));
+            }
+            Ok(None) => {}
+            Err(e) => return Err(format!("Error executing command: {}", e)),
+        }
+
+        if let Ok(n) = reader.read(&mut buf) {
+            if n > 0 {
+                output.extend_from_slice(&buf[..n]);
+            }
+        }
+
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+/// Async wrapper around [`run_pty`]: runs it on a blocking thread, enforces
+/// `timeout_secs`, and signals the blocking task to kill the PTY's child
+/// instead of leaving it running when the timeout fires.
+async fn execute_pty(
+    command: String,
+    cwd: PathBuf,
+    rows: u16,
+    cols: u16,
+    timeout_secs: u64,
+    env_allowlist: Option<Vec<(String, String)>>,
+) -> String {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let task_cancel = cancel.clone();
+    let handle = tokio::task::spawn_blocking(move || {
+        run_pty(command, cwd, rows, cols, task_cancel, env_allowlist)
+    });
+
+    let result = match timeout(Duration::from_secs(timeout_secs), handle).await {
+        Ok(join_result) => {
+            join_result.unwrap_or_else(|_| Err("Error executing command: pty task panicked".to_string()))
+        }
+        Err(_) => {
+            cancel.store(true, Ordering::Relaxed);
+            return format!("Error: Command timed out after {} seconds", timeout_secs);
+        }
+    };
+
+    match result {
+        Ok((output, code)) => {
+            let mut result = output;
+            if code != 0 {
+                result.push_str(&format!("\nExit code: {}", code));
+            }
+            if result.trim().is_empty() {
+                result = "(no output)".to_string();
+            }
+
+            const MAX_LEN: usize = 10000;
+            if result.len() > MAX_LEN {
+                format!(
+                    "{}... (truncated, {} more chars)",
+                    &result[..MAX_LEN],
+                    result.len() - MAX_LEN
+                )
+            } else {
+                result
+            }
+        }
+        Err(e) => e,
+    }
+}
+
+/// Characters that give `sh` special meaning; a command containing any of
+/// these can't safely run outside `sh -c`, so [`CommandPolicy::evaluate`]
+/// treats their presence as "needs the shell" rather than attempting a
+/// best-effort direct exec.
+const SHELL_METACHARS: &[char] = &[
+    ';', '&', '|', '<', '>', '(', ')', '$', '`', '\n', '*', '?', '~', '#', '!', '"', '\'', '\\',
+];
+
+/// Single-`*`-wildcard glob match against a parsed program name, e.g.
+/// `"git"` or `"/usr/bin/*"`.
+///
+/// Duplicated from the equivalent matcher in `context.rs`/`filesystem.rs`
+/// rather than shared, matching this crate's existing convention of small
+/// per-file utilities over a shared `mod utils`.
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, suffix)) => {
+            name.len() >= prefix.len() + suffix.len()
+                && name.starts_with(prefix)
+                && name.ends_with(suffix)
+        }
+    }
+}
+
+/// Split `command` into whitespace-separated words, honoring single and
+/// double quotes (but not escapes or nesting) - enough to recover the
+/// program name and argv for policy checks and direct (non-shell) exec.
+fn split_command_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    for ch in command.chars() {
+        match quote {
+            Some(q) if ch == q => quote = None,
+            Some(_) => current.push(ch),
+            None if ch == '\'' || ch == '"' => quote = Some(ch),
+            None if ch.is_whitespace() => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+            }
+            None => current.push(ch),
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// How [`CommandPolicy::evaluate`] decided a command should be spawned.
+enum ExecPlan {
+    /// Run as usual, via `sh -c`/`cmd /C`.
+    Shell,
+    /// The policy forbids shell metacharacters and the command doesn't use
+    /// any - run the parsed program directly via `Command::new(program)`
+    /// instead, so there's no shell to smuggle anything past the policy.
+    Direct { program: String, args: Vec<String> },
+}
+
+/// Policy controls applied before `ExecTool` spawns a command, so an
+/// operator can scope what an LLM agent's `exec` calls can actually do
+/// instead of relying on the tool description's "Use with caution." A
+/// tool with no policy (the default) behaves exactly as before.
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct CommandPolicy {
+    allow: Vec<String>,
+    deny: Vec<String>,
+    max_args: Option<usize>,
+    max_command_len: Option<usize>,
+    forbid_shell_metachars: bool,
+    env_allowlist: Option<Vec<String>>,
+}
+
+#[pymethods]
+impl CommandPolicy {
+    /// `allow`/`deny` are single-`*`-wildcard glob patterns matched against
+    /// the parsed program name (not the full command line), e.g. `"git"`
+    /// or `"/usr/bin/*"`. An empty `allow` allows every program unless it
+    /// matches `deny`; `deny` is checked first. `env_allowlist`, when set,
+    /// scrubs every inherited environment variable except the ones named.
+    #[new]
+    #[pyo3(signature = (allow=Vec::new(), deny=Vec::new(), max_args=None, max_command_len=None, forbid_shell_metachars=false, env_allowlist=None))]
+    fn new(
+        allow: Vec<String>,
+        deny: Vec<String>,
+        max_args: Option<usize>,
+        max_command_len: Option<usize>,
+        forbid_shell_metachars: bool,
+        env_allowlist: Option<Vec<String>>,
+    ) -> Self {
+        Self {
+            allow,
+            deny,
+            max_args,
+            max_command_len,
+            forbid_shell_metachars,
+            env_allowlist,
+        }
+    }
+}
+
+impl CommandPolicy {
+    /// Check `command` against this policy, returning the plan to spawn it
+    /// with, or `Err(reason)` describing why it's blocked.
+    fn evaluate(&self, command: &str) -> Result<ExecPlan, String> {
+        if let Some(max_len) = self.max_command_len {
+            if command.len() > max_len {
+                return Err(format!(
+                    "command length {} exceeds policy maximum of {} characters",
+                    command.len(),
+                    max_len
+                ));
+            }
+        }
+
+        let words = split_command_words(command);
+        let program = words.first().cloned().unwrap_or_default();
+        let program_name = Path::new(&program)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| program.clone());
+
+        if let Some(max_args) = self.max_args {
+            let arg_count = words.len().saturating_sub(1);
+            if arg_count > max_args {
+                return Err(format!(
+                    "command has {} arguments, exceeding policy maximum of {}",
+                    arg_count, max_args
+                ));
+            }
+        }
+
+        if self.deny.iter().any(|p| matches_glob(p, &program_name)) {
+            return Err(format!("program '{}' is denied by policy", program_name));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|p| matches_glob(p, &program_name)) {
+            return Err(format!(
+                "program '{}' is not in the policy allowlist",
+                program_name
+            ));
+        }
+
+        if self.forbid_shell_metachars {
+            if command.chars().any(|c| SHELL_METACHARS.contains(&c)) {
+                return Err(
+                    "command contains shell metacharacters, which are forbidden by policy"
+                        .to_string(),
+                );
+            }
+            let mut words = words;
+            if words.is_empty() {
+                return Err("command is empty".to_string());
+            }
+            let args = words.split_off(1);
+            return Ok(ExecPlan::Direct {
+                program: words.remove(0),
+                args,
+            });
+        }
+
+        Ok(ExecPlan::Shell)
+    }
+
+    /// The environment to give the child when `env_allowlist` is set: only
+    /// the listed variables, pulled from this process's own environment.
+    /// `None` means no allowlist was configured, so the child inherits the
+    /// full parent environment as usual.
+    fn filtered_env(&self) -> Option<Vec<(String, String)>> {
+        let allowlist = self.env_allowlist.as_ref()?;
+        Some(
+            std::env::vars()
+                .filter(|(k, _)| allowlist.iter().any(|a| a == k))
+                .collect(),
+        )
+    }
+
+    /// Evaluate `command` against this policy, returning the `(direct,
+    /// env)` pair to pass to [`spawn_streaming`], or the "blocked by
+    /// policy" message to return instead of spawning anything. Shared by
+    /// every entry point that can spawn a command under a `CommandPolicy`
+    /// (`ExecTool::resolve_policy`, `JobManager::submit`) so none of them
+    /// can bypass it.
+    pub(crate) fn resolve(
+        &self,
+        command: &str,
+    ) -> Result<(Option<(String, Vec<String>)>, Option<Vec<(String, String)>>), String> {
+        match self.evaluate(command) {
+            Err(reason) => Err(format!("command blocked by policy: {}", reason)),
+            Ok(ExecPlan::Shell) => Ok((None, self.filtered_env())),
+            Ok(ExecPlan::Direct { program, args }) => Ok((Some((program, args)), self.filtered_env())),
+        }
+    }
+}
 
 /// Tool to execute shell commands.
 #[pyclass]
@@ -16,6 +686,10 @@ use super::base::{object_schema, string_prop, Tool, ToolSchema};
 pub struct ExecTool {
     timeout_secs: u64,
     working_dir: Option<String>,
+    pty: bool,
+    pty_rows: u16,
+    pty_cols: u16,
+    policy: Option<CommandPolicy>,
 }
 
 impl Tool for ExecTool {
@@ -37,8 +711,52 @@ impl Tool for ExecTool {
             "working_dir".into(),
             string_prop("Optional working directory for the command"),
         );
+        props.insert(
+            "pty".into(),
+            serde_json::json!({
+                "type": "boolean",
+                "description": "Run attached to a pseudo-terminal instead of plain pipes, for TTY-dependent commands (default: the tool's own 'pty' constructor flag)"
+            }),
+        );
+        props.insert(
+            "rows".into(),
+            int_prop("Terminal rows when 'pty' is true (default 24)"),
+        );
+        props.insert(
+            "cols".into(),
+            int_prop("Terminal columns when 'pty' is true (default 80)"),
+        );
+        props.insert(
+            "term_signal".into(),
+            string_prop("Signal sent to the command's process group on timeout or cancellation, e.g. 'SIGTERM', 'SIGKILL' (default 'SIGTERM'); escalates to SIGKILL after a grace period if the group is still alive. Not used when 'pty' is true"),
+        );
+        props.insert(
+            "stdin".into(),
+            string_prop("Text written to the command's stdin, then closed, before its output is read. Not used when 'pty' is true"),
+        );
+        props.insert(
+            "env".into(),
+            serde_json::json!({
+                "type": "object",
+                "additionalProperties": {"type": "string"},
+                "description": "Extra environment variables merged over the inherited environment (or the policy's env_allowlist, if set), e.g. to run with a specific PATH. Passed as a JSON object when calling through the generic tool registry. Not used when 'pty' is true"
+            }),
+        );
+        props.insert(
+            "timeout_secs".into(),
+            int_prop("Per-call timeout override in seconds (default: the tool's own 'timeout' constructor value)"),
+        );
         object_schema(props, vec!["command"])
     }
+
+    fn conversions(&self) -> HashMap<String, Conversion> {
+        let mut map = HashMap::new();
+        map.insert("pty".to_string(), Conversion::Boolean);
+        map.insert("rows".to_string(), Conversion::Integer);
+        map.insert("cols".to_string(), Conversion::Integer);
+        map.insert("timeout_secs".to_string(), Conversion::Integer);
+        map
+    }
 }
 
 impl ExecTool {
@@ -50,96 +768,161 @@ impl ExecTool {
         Tool::to_schema(self, py)
     }
 
-    pub async fn execute_inner(&self, params: &HashMap<String, String>) -> String {
-        let command = match params.get("command") {
+    pub fn conversions(&self) -> HashMap<String, Conversion> {
+        Tool::conversions(self)
+    }
+
+    /// Evaluate `self.policy` (if any) against `command`, returning the
+    /// `(direct, env)` pair to pass to [`spawn_streaming`], or the
+    /// "blocked by policy" message to return instead of spawning anything.
+    fn resolve_policy(
+        &self,
+        command: &str,
+    ) -> Result<(Option<(String, Vec<String>)>, Option<Vec<(String, String)>>), String> {
+        let Some(policy) = &self.policy else {
+            return Ok((None, None));
+        };
+        policy
+            .resolve(command)
+            .map_err(|reason| format!("Error: {}", reason))
+    }
+
+    pub async fn execute_inner(
+        &self,
+        params: &HashMap<String, TypedValue>,
+        on_chunk: Option<ChunkSender>,
+    ) -> String {
+        let command = match params.get("command").and_then(|v| v.as_str()) {
             Some(c) => c,
             None => return "Error: Missing required parameter 'command'".to_string(),
         };
 
-        let cwd = params
-            .get("working_dir")
-            .map(|s| s.as_str())
-            .or(self.working_dir.as_deref())
-            .map(|s| {
-                if let Some(stripped) = s.strip_prefix("~/") {
-                    if let Some(home) = dirs::home_dir() {
-                        return home.join(stripped);
-                    }
-                }
-                PathBuf::from(s)
-            })
-            .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+        let cwd = resolve_cwd(
+            params.get("working_dir").and_then(|v| v.as_str()),
+            self.working_dir.as_deref(),
+        );
 
-        // Create shell command
-        let mut cmd = if cfg!(target_os = "windows") {
-            let mut c = Command::new("cmd");
-            c.args(["/C", command]);
-            c
-        } else {
-            let mut c = Command::new("sh");
-            c.args(["-c", command]);
-            c
+        let (direct, env_allowlist) = match self.resolve_policy(command) {
+            Ok(plan) => plan,
+            Err(msg) => return msg,
         };
 
-        cmd.current_dir(&cwd);
-        cmd.stdout(std::process::Stdio::piped());
-        cmd.stderr(std::process::Stdio::piped());
+        let pty = params.get("pty").and_then(|v| v.as_bool()).unwrap_or(self.pty);
+        if pty {
+            let rows = params
+                .get("rows")
+                .and_then(|v| v.as_i64())
+                .map(|n| n.max(1) as u16)
+                .unwrap_or(self.pty_rows);
+            let cols = params
+                .get("cols")
+                .and_then(|v| v.as_i64())
+                .map(|n| n.max(1) as u16)
+                .unwrap_or(self.pty_cols);
+            let timeout_secs = params
+                .get("timeout_secs")
+                .and_then(|v| v.as_i64())
+                .map(|n| n.max(0) as u64)
+                .unwrap_or(self.timeout_secs);
+            return execute_pty(command.to_string(), cwd, rows, cols, timeout_secs, env_allowlist).await;
+        }
 
-        // Execute with timeout
-        let result = timeout(Duration::from_secs(self.timeout_secs), async {
-            match cmd.output().await {
-                Ok(output) => {
-                    let mut parts = Vec::new();
+        let env_overrides = match params.get("env").and_then(|v| v.as_str()) {
+            Some(raw) => match serde_json::from_str::<HashMap<String, String>>(raw) {
+                Ok(map) => Some(map.into_iter().collect()),
+                Err(e) => return format!("Error: invalid 'env' JSON: {}", e),
+            },
+            None => None,
+        };
 
-                    // stdout
-                    if !output.stdout.is_empty() {
-                        let stdout = String::from_utf8_lossy(&output.stdout);
-                        parts.push(stdout.to_string());
-                    }
+        let term_signal = params
+            .get("term_signal")
+            .and_then(|v| v.as_str())
+            .map(parse_signal)
+            .unwrap_or_else(|| parse_signal("SIGTERM"));
+        let timeout_secs = params
+            .get("timeout_secs")
+            .and_then(|v| v.as_i64())
+            .map(|n| n.max(0) as u64)
+            .unwrap_or(self.timeout_secs);
+        let (mut rx, _cancel) = spawn_streaming(
+            command.to_string(),
+            cwd,
+            timeout_secs,
+            term_signal,
+            SpawnOptions {
+                direct,
+                env_allowlist,
+                env_overrides,
+                stdin: params.get("stdin").and_then(|v| v.as_str()).map(String::from),
+            },
+        );
 
-                    // stderr
-                    if !output.stderr.is_empty() {
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-                        if !stderr.trim().is_empty() {
-                            parts.push(format!("STDERR:\n{}", stderr));
-                        }
-                    }
+        let mut stdout_collected = String::new();
+        let mut stderr_collected = String::new();
+        let mut exit_code: Option<i32> = None;
+        let mut error: Option<String> = None;
 
-                    // Exit code if non-zero
-                    if !output.status.success() {
-                        let code = output.status.code().unwrap_or(-1);
-                        parts.push(format!("\nExit code: {}", code));
+        while let Some(event) = rx.recv().await {
+            match event {
+                StreamEvent::Stdout(line) => {
+                    if let Some(tx) = &on_chunk {
+                        let _ = tx.send(line.clone());
                     }
-
-                    let result = if parts.is_empty() {
-                        "(no output)".to_string()
-                    } else {
-                        parts.join("\n")
-                    };
-
-                    // Truncate very long output
-                    const MAX_LEN: usize = 10000;
-                    if result.len() > MAX_LEN {
-                        format!(
-                            "{}... (truncated, {} more chars)",
-                            &result[..MAX_LEN],
-                            result.len() - MAX_LEN
-                        )
-                    } else {
-                        result
+                    stdout_collected.push_str(&line);
+                    stdout_collected.push('\n');
+                }
+                StreamEvent::Stderr(line) => {
+                    if let Some(tx) = &on_chunk {
+                        let _ = tx.send(format!("STDERR: {}", line));
                     }
+                    stderr_collected.push_str(&line);
+                    stderr_collected.push('\n');
                 }
-                Err(e) => format!("Error executing command: {}", e),
+                StreamEvent::Exit(code) => exit_code = Some(code),
+                StreamEvent::Error(msg) => error = Some(msg),
             }
-        })
-        .await;
+        }
 
-        match result {
-            Ok(output) => output,
-            Err(_) => format!(
-                "Error: Command timed out after {} seconds",
-                self.timeout_secs
-            ),
+        if let Some(msg) = error {
+            return msg;
+        }
+
+        let mut parts = Vec::new();
+
+        if !stdout_collected.trim().is_empty() {
+            parts.push(stdout_collected.trim_end_matches('\n').to_string());
+        }
+
+        if !stderr_collected.trim().is_empty() {
+            parts.push(format!(
+                "STDERR:\n{}",
+                stderr_collected.trim_end_matches('\n')
+            ));
+        }
+
+        if let Some(code) = exit_code {
+            if code != 0 {
+                parts.push(format!("\nExit code: {}", code));
+            }
+        }
+
+        let result = if parts.is_empty() {
+            "(no output)".to_string()
+        } else {
+            parts.join("\n")
+        };
+
+        // Truncate very long output
+        const MAX_LEN: usize = 10000;
+        if result.len() > MAX_LEN {
+            format!(
+                "{}... (truncated, {} more chars)",
+                &result[..MAX_LEN],
+                result.len() - MAX_LEN
+            )
+        } else {
+            result
         }
     }
 }
@@ -147,11 +930,22 @@ impl ExecTool {
 #[pymethods]
 impl ExecTool {
     #[new]
-    #[pyo3(signature = (timeout=60, working_dir=None))]
-    fn new(timeout: u64, working_dir: Option<String>) -> Self {
+    #[pyo3(signature = (timeout=60, working_dir=None, pty=false, pty_rows=24, pty_cols=80, policy=None))]
+    fn new(
+        timeout: u64,
+        working_dir: Option<String>,
+        pty: bool,
+        pty_rows: u16,
+        pty_cols: u16,
+        policy: Option<CommandPolicy>,
+    ) -> Self {
         Self {
             timeout_secs: timeout,
             working_dir,
+            pty,
+            pty_rows,
+            pty_cols,
+            policy,
         }
     }
 
@@ -174,21 +968,96 @@ impl ExecTool {
         Ok(result.into())
     }
 
-    #[pyo3(signature = (command, working_dir=None))]
+    #[pyo3(signature = (command, working_dir=None, pty=None, rows=None, cols=None, term_signal=None, stdin=None, env=None, timeout_secs=None))]
     fn execute<'py>(
         &self,
         py: Python<'py>,
         command: String,
         working_dir: Option<String>,
+        pty: Option<bool>,
+        rows: Option<i64>,
+        cols: Option<i64>,
+        term_signal: Option<String>,
+        stdin: Option<String>,
+        env: Option<HashMap<String, String>>,
+        timeout_secs: Option<i64>,
     ) -> PyResult<Bound<'py, PyAny>> {
+        let env_json = env
+            .map(|e| {
+                serde_json::to_string(&e).map_err(|e| {
+                    pyo3::exceptions::PyValueError::new_err(format!("invalid 'env': {}", e))
+                })
+            })
+            .transpose()?;
+
         let this = self.clone();
         future_into_py(py, async move {
             let mut params = HashMap::new();
-            params.insert("command".to_string(), command);
+            params.insert("command".to_string(), TypedValue::String(command));
             if let Some(wd) = working_dir {
-                params.insert("working_dir".to_string(), wd);
+                params.insert("working_dir".to_string(), TypedValue::String(wd));
+            }
+            if let Some(pty) = pty {
+                params.insert("pty".to_string(), TypedValue::Boolean(pty));
+            }
+            if let Some(n) = rows {
+                params.insert("rows".to_string(), TypedValue::Integer(n));
+            }
+            if let Some(n) = cols {
+                params.insert("cols".to_string(), TypedValue::Integer(n));
+            }
+            if let Some(sig) = term_signal {
+                params.insert("term_signal".to_string(), TypedValue::String(sig));
+            }
+            if let Some(data) = stdin {
+                params.insert("stdin".to_string(), TypedValue::String(data));
+            }
+            if let Some(json) = env_json {
+                params.insert("env".to_string(), TypedValue::String(json));
+            }
+            if let Some(n) = timeout_secs {
+                params.insert("timeout_secs".to_string(), TypedValue::Integer(n));
             }
-            Ok(this.execute_inner(&params).await)
+            Ok(this.execute_inner(&params, None).await)
+        })
+    }
+
+    /// Like `execute`, but returns an async iterator yielding
+    /// `{stream: "stdout"|"stderr", data: str}` events live as the command
+    /// runs, followed by a final `{exit_code: int}` (or `{error: str}`)
+    /// event, instead of waiting for the command to finish. The returned
+    /// `ExecStream` also exposes `cancel()` to stop the command early.
+    /// Raises `RuntimeError` if a `CommandPolicy` is configured and blocks
+    /// `command`, the same check `execute` applies before spawning.
+    #[pyo3(signature = (command, working_dir=None, term_signal=None))]
+    fn execute_stream(
+        &self,
+        command: String,
+        working_dir: Option<String>,
+        term_signal: Option<String>,
+    ) -> PyResult<ExecStream> {
+        let (direct, env_allowlist) = self
+            .resolve_policy(&command)
+            .map_err(pyo3::exceptions::PyRuntimeError::new_err)?;
+        let cwd = resolve_cwd(working_dir.as_deref(), self.working_dir.as_deref());
+        let term_signal = term_signal
+            .as_deref()
+            .map(parse_signal)
+            .unwrap_or_else(|| parse_signal("SIGTERM"));
+        let (rx, cancel) = spawn_streaming(
+            command,
+            cwd,
+            self.timeout_secs,
+            term_signal,
+            SpawnOptions {
+                direct,
+                env_allowlist,
+                ..Default::default()
+            },
+        );
+        Ok(ExecStream {
+            rx: Arc::new(Mutex::new(rx)),
+            cancel,
         })
     }
 